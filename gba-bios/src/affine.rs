@@ -0,0 +1,190 @@
+use gba_hw::video::BackgroundAffine;
+
+/// Parameters for rotating and scaling a background with
+/// `bg_affine_set`.
+///
+/// The texture point `(texture_x, texture_y)` is drawn at the screen
+/// point `(screen_x, screen_y)`, with the rest of the background
+/// scaled and rotated around it.
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct BgAffineSource {
+    /// Texture x of the center (20.8 fixed point)
+    pub texture_x: i32,
+
+    /// Texture y of the center (20.8 fixed point)
+    pub texture_y: i32,
+
+    /// Screen x of the center, in pixels
+    pub screen_x: i16,
+
+    /// Screen y of the center, in pixels
+    pub screen_y: i16,
+
+    /// Horizontal scale (8.8 fixed point), larger values shrink the
+    /// background
+    pub scale_x: i16,
+
+    /// Vertical scale (8.8 fixed point), larger values shrink the
+    /// background
+    pub scale_y: i16,
+
+    /// Counter-clockwise rotation, where `0x10000` is a full turn.
+    /// Only the upper 8 bits are used.
+    pub angle: u16,
+}
+
+/// The output of `bg_affine_set`, laid out to match the background
+/// affine registers.
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct BgAffineDest {
+    /// Texture x change per screen pixel (8.8 fixed point)
+    pub pa: i16,
+    /// Texture x change per screen line (8.8 fixed point)
+    pub pb: i16,
+    /// Texture y change per screen pixel (8.8 fixed point)
+    pub pc: i16,
+    /// Texture y change per screen line (8.8 fixed point)
+    pub pd: i16,
+    /// Texture x at the top left of the screen (20.8 fixed point)
+    pub x: i32,
+    /// Texture y at the top left of the screen (20.8 fixed point)
+    pub y: i32,
+}
+
+/// Parameters for rotating and scaling an object with
+/// `obj_affine_set`.
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct ObjAffineSource {
+    /// Horizontal scale (8.8 fixed point), larger values shrink the
+    /// object
+    pub scale_x: i16,
+
+    /// Vertical scale (8.8 fixed point), larger values shrink the
+    /// object
+    pub scale_y: i16,
+
+    /// Counter-clockwise rotation, where `0x10000` is a full turn.
+    /// Only the upper 8 bits are used.
+    pub angle: u16,
+
+    _padding: u16,
+}
+
+impl ObjAffineSource {
+    /// Creates object affine parameters.
+    #[inline]
+    pub fn new(scale_x: i16, scale_y: i16, angle: u16) -> ObjAffineSource {
+        ObjAffineSource {
+            scale_x: scale_x,
+            scale_y: scale_y,
+            angle: angle,
+            _padding: 0,
+        }
+    }
+}
+
+/// An object affine matrix (all 8.8 fixed point).
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct ObjAffineMatrix {
+    /// Texture x change per screen pixel
+    pub pa: i16,
+    /// Texture x change per screen line
+    pub pb: i16,
+    /// Texture y change per screen pixel
+    pub pc: i16,
+    /// Texture y change per screen line
+    pub pd: i16,
+}
+
+/// Number of affine matrices in OAM.
+pub const OAM_AFFINE_MATRICES: usize = 32;
+
+// Bytes between matrix elements when writing into RAM and into OAM,
+// where the elements are interleaved with object attributes.
+const RAM_STRIDE: usize = 2;
+const OAM_STRIDE: usize = 8;
+
+#[inline(always)]
+unsafe fn swi_bg_affine_set(source: *const BgAffineSource, dest: *mut BgAffineDest, count: usize) {
+    asm!(swi!(BgAffineSet)
+         :
+         : "{r0}"(source)
+         , "{r1}"(dest)
+         , "{r2}"(count)
+         : "r0", "r1", "r2", "r3", "memory"
+         : "volatile");
+}
+
+#[inline(always)]
+unsafe fn swi_obj_affine_set(source: *const ObjAffineSource, dest: *mut i16,
+                             count: usize, stride: usize) {
+    asm!(swi!(ObjAffineSet)
+         :
+         : "{r0}"(source)
+         , "{r1}"(dest)
+         , "{r2}"(count)
+         , "{r3}"(stride)
+         : "r0", "r1", "r2", "r3", "memory"
+         : "volatile");
+}
+
+/// Calculates background affine parameters for each source.
+///
+/// # Panics
+///
+/// If `source` and `dest` have different lengths.
+#[inline]
+pub fn bg_affine_set(source: &[BgAffineSource], dest: &mut [BgAffineDest]) {
+    assert_eq!(source.len(), dest.len());
+    unsafe { swi_bg_affine_set(source.as_ptr(), dest.as_mut_ptr(), source.len()); }
+}
+
+/// Calculates background affine parameters directly into a
+/// background's affine registers.
+#[inline]
+pub fn bg_affine_set_registers(source: &BgAffineSource, dest: &mut BackgroundAffine) {
+    unsafe { swi_bg_affine_set(source, dest as *mut BackgroundAffine as *mut BgAffineDest, 1); }
+}
+
+/// Calculates an object affine matrix for each source.
+///
+/// # Panics
+///
+/// If `source` and `dest` have different lengths.
+#[inline]
+pub fn obj_affine_set(source: &[ObjAffineSource], dest: &mut [ObjAffineMatrix]) {
+    assert_eq!(source.len(), dest.len());
+    unsafe {
+        swi_obj_affine_set(source.as_ptr(), dest.as_mut_ptr() as *mut i16,
+                           source.len(), RAM_STRIDE);
+    }
+}
+
+/// Calculates object affine matrices into OAM, starting at matrix
+/// `first`.
+///
+/// # Arguments
+///
+/// * `source` parameters for each matrix
+/// * `oam` all of OAM as halfwords, or a shadow copy of it in RAM
+/// * `first` index of the first matrix to write
+///
+/// # Panics
+///
+/// If the matrices don't fit in `oam`, or past `OAM_AFFINE_MATRICES`.
+#[inline]
+pub fn obj_affine_set_oam(source: &[ObjAffineSource], oam: &mut [u16], first: usize) {
+    // Each matrix is spread across four objects, 16 halfwords in all,
+    // taking the last halfword of each.
+    let end = first + source.len();
+    assert!(end <= OAM_AFFINE_MATRICES);
+    assert!(oam.len() >= end * 16);
+    unsafe {
+        let dest = oam.as_mut_ptr().offset(first as isize * 16 + 3) as *mut i16;
+        swi_obj_affine_set(source.as_ptr(), dest, source.len(), OAM_STRIDE);
+    }
+}
@@ -5,21 +5,11 @@
 ///
 /// ```
 /// use gba_bios::div_modulo_absdiv;
-/// assert_eq!((-123, -3, 123), div_modulo_absdiv(-1234, 10));
+/// assert_eq!((-123, -4, 123), div_modulo_absdiv(-1234, 10));
 /// ```
 #[inline(always)]
 pub fn div_modulo_absdiv(num: i32, denom: i32) -> (i32, i32, i32) {
-    let div: i32;
-    let modulo: i32;
-    let abs_div: i32;
-    unsafe { asm!("swi 0x06"
-        :"={r0}"(div),
-         "={r1}"(modulo),
-         "={r3}"(abs_div)
-        :"{r0}"(num),
-         "{r1}"(denom)
-    );}
-    (div, modulo, abs_div)
+    ::sys::div_modulo_absdiv(num, denom)
 }
 
 /// Calculates the square root.
@@ -32,12 +22,7 @@ pub fn div_modulo_absdiv(num: i32, denom: i32) -> (i32, i32, i32) {
 /// ```
 #[inline(always)]
 pub fn sqrt(x: u32) -> u16 {
-    let out: u16;
-    unsafe { asm!("swi 0x08"
-         :  "={r0}"(out)
-         :  "{r0}"(x)
-    );}
-    out
+    ::sys::sqrt(x)
 }
 
 /// Calculates arctangent, returns a fixed point signed Q1.14 `t` s.t.
@@ -52,16 +37,11 @@ pub fn sqrt(x: u32) -> u16 {
 /// ```
 /// use gba_bios::arctan;
 /// assert_eq!(0, arctan(0));
-/// assert_eq!(0x2000000, arctan(1 << 14));
+/// assert_eq!(0x2000, arctan(1 << 14));
 /// ```
 #[inline(always)]
 pub fn arctan(x: i16) -> i16 {
-    let out: i16;
-    unsafe { asm!("swi 0x09"
-         :  "={r0}"(out)
-         :  "{r0}"(x)
-    );}
-    out
+    ::sys::arctan(x)
 }
 
 /// Calculates arctan2 returns a fixed point unsigned Q0.16 `t` s.t.
@@ -77,15 +57,11 @@ pub fn arctan(x: i16) -> i16 {
 /// ```
 /// use gba_bios::arctan2;
 /// assert_eq!(0, arctan2(1 << 14, 0));
-/// assert_eq!(0x4000000, arctan2(0, 1 << 14));
+/// assert_eq!(0x4000, arctan2(0, 1 << 14));
+/// assert_eq!(0x8000, arctan2(-1 << 14, 0));
 /// ```
 #[inline(always)]
-pub fn arctan2(x: i16, y: i16) -> i16 {
-    let out: i16;
-    unsafe { asm!("swi 0x0A"
-         :  "={r0}"(out)
-         :  "{r0}"(x), "{r1}"(y)
-    );}
-    out
+pub fn arctan2(x: i16, y: i16) -> u16 {
+    ::sys::arctan2(x, y)
 }
 
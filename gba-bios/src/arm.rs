@@ -0,0 +1,99 @@
+//! BIOS calls, on the game boy advance itself.
+
+use decompress::DecompressError;
+
+#[inline(always)]
+pub fn div_modulo_absdiv(num: i32, denom: i32) -> (i32, i32, i32) {
+    let div: i32;
+    let modulo: i32;
+    let abs_div: i32;
+    unsafe { asm!(swi!(Div)
+        :"={r0}"(div),
+         "={r1}"(modulo),
+         "={r3}"(abs_div)
+        :"{r0}"(num),
+         "{r1}"(denom)
+    );}
+    (div, modulo, abs_div)
+}
+
+#[inline(always)]
+pub fn sqrt(x: u32) -> u16 {
+    let out: u16;
+    unsafe { asm!(swi!(Sqrt)
+         :  "={r0}"(out)
+         :  "{r0}"(x)
+         :  "r1", "r3"
+    );}
+    out
+}
+
+#[inline(always)]
+pub fn arctan(x: i16) -> i16 {
+    let out: i16;
+    unsafe { asm!(swi!(ArcTan)
+         :  "={r0}"(out)
+         :  "{r0}"(x)
+         :  "r1", "r3"
+    );}
+    out
+}
+
+#[inline(always)]
+pub fn arctan2(x: i16, y: i16) -> u16 {
+    let out: u16;
+    unsafe { asm!(swi!(ArcTan2)
+         :  "={r0}"(out)
+         :  "{r0}"(x), "{r1}"(y)
+         :  "r1", "r3"
+    );}
+    out
+}
+
+#[inline(always)]
+pub unsafe fn cpu_set(source: *const u8, dest: *mut u8, control: u32) {
+    asm!(swi!(CpuSet)
+         :
+         : "{r0}"(source)
+         , "{r1}"(dest)
+         , "{r2}"(control)
+         : "r0", "r1", "r2", "r3", "memory"
+         : "volatile");
+}
+
+#[inline(always)]
+pub unsafe fn cpu_fast_set(source: *const u32, dest: *mut u32, control: u32) {
+    asm!(swi!(CpuFastSet)
+         :
+         : "{r0}"(source)
+         , "{r1}"(dest)
+         , "{r2}"(control)
+         : "r0", "r1", "r2", "r3", "memory"
+         : "volatile");
+}
+
+// The BIOS can't be stopped at the end of `source` or `dest`, so
+// callers must have checked the data.
+macro_rules! decompress_call {
+    ($name:ident, $swi:ident) => {
+        #[inline(always)]
+        pub unsafe fn $name(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+            asm!(swi!($swi)
+                 :
+                 : "{r0}"(source.as_ptr())
+                 , "{r1}"(dest.as_mut_ptr())
+                 : "r0", "r1", "r2", "r3", "memory"
+                 : "volatile");
+            Ok(())
+        }
+    };
+}
+
+decompress_call!(lz77_uncompress_wram, Lz77UnCompWram);
+decompress_call!(lz77_uncompress_vram, Lz77UnCompVram);
+decompress_call!(huffman_uncompress, HuffUnComp);
+decompress_call!(run_length_uncompress_wram, RlUnCompWram);
+decompress_call!(run_length_uncompress_vram, RlUnCompVram);
+decompress_call!(diff8_unfilter_wram, Diff8bitUnFilterWram);
+decompress_call!(diff8_unfilter_vram, Diff8bitUnFilterVram);
+decompress_call!(diff16_unfilter, Diff16bitUnFilter);
@@ -0,0 +1,313 @@
+use core::{cmp, mem, slice};
+use stream::{self, Count};
+
+/// Data formats understood by the BIOS decompression functions.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    /// LZ77, header type `0x10`.
+    Lz77,
+
+    /// Huffman with 4-bit symbols, header type `0x24`.
+    Huffman4,
+
+    /// Huffman with 8-bit symbols, header type `0x28`.
+    Huffman8,
+
+    /// Run length encoding, header type `0x30`.
+    RunLength,
+
+    /// 8-bit difference filter, header type `0x81`.
+    Diff8,
+
+    /// 16-bit difference filter, header type `0x82`.
+    Diff16,
+}
+
+/// The 32-bit header that starts all compressed data.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Header {
+    /// How the data is compressed.
+    pub format: Format,
+
+    /// Size of the decompressed data in bytes.
+    pub size: usize,
+}
+
+impl Header {
+    /// Parses a header word.
+    pub fn parse(word: u32) -> Result<Header, DecompressError> {
+        let format = match word & 0xFF {
+            0x10 => Format::Lz77,
+            0x24 => Format::Huffman4,
+            0x28 => Format::Huffman8,
+            0x30 => Format::RunLength,
+            0x81 => Format::Diff8,
+            0x82 => Format::Diff16,
+            _ => return Err(DecompressError::UnknownFormat(word as u8)),
+        };
+        Ok(Header { format: format, size: (word >> 8) as usize })
+    }
+}
+
+/// Errors from the decompression functions.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DecompressError {
+    /// The compressed data was empty, so had no header.
+    MissingHeader,
+
+    /// The header's type byte isn't one the BIOS understands.
+    UnknownFormat(u8),
+
+    /// The data is valid, but in a different format than the
+    /// function expects.
+    WrongFormat(Format),
+
+    /// The data ends before the decompressed size is reached.
+    Truncated,
+
+    /// An LZ77 back-reference points before the start of the
+    /// decompressed data.
+    InvalidReference,
+
+    /// A Huffman node points outside the tree.
+    InvalidTree,
+
+    /// The destination can't hold the decompressed data.
+    DestinationTooSmall {
+        /// Bytes needed, including rounding up to the unit size and
+        /// any written past the decompressed size.
+        needed: usize,
+        /// Bytes available.
+        available: usize,
+    },
+}
+
+/// Compressed data that the BIOS can decompress without reading past
+/// its end.
+///
+/// The BIOS requires compressed data to be word aligned, which
+/// is guaranteed by storing it as `u32`s.
+#[derive(Copy, Clone, Debug)]
+pub struct Compressed<'a> {
+    data: &'a [u32],
+    header: Header,
+    written: usize,
+}
+
+impl<'a> Compressed<'a> {
+    /// Wraps compressed data after checking it.
+    ///
+    /// Checking means going through all of the data the way the BIOS
+    /// will, so for large data known to be good, like the output of
+    /// `gba-compress` included in the ROM, `new_unchecked` is faster.
+    pub fn new(data: &'a [u32]) -> Result<Compressed<'a>, DecompressError> {
+        let header = match data.first() {
+            Some(&word) => Header::parse(word)?,
+            None => return Err(DecompressError::MissingHeader),
+        };
+        let mut count = Count { len: 0 };
+        stream::walk(data, header, &mut count)?;
+        Ok(Compressed { data: data, header: header, written: count.len })
+    }
+
+    /// Wraps compressed data after checking only its header.
+    ///
+    /// # Safety
+    ///
+    /// The data must decompress to exactly the size in its header
+    /// without ending early, as the output of `gba-compress` does.  In
+    /// particular, LZ77 back-references must not point before the
+    /// start of the decompressed data, and Huffman nodes must not point
+    /// outside the tree.  Otherwise the BIOS reads and writes outside
+    /// the data and the destination.
+    pub unsafe fn new_unchecked(data: &'a [u32])
+                                -> Result<Compressed<'a>, DecompressError> {
+        let header = match data.first() {
+            Some(&word) => Header::parse(word)?,
+            None => return Err(DecompressError::MissingHeader),
+        };
+        Ok(Compressed { data: data, header: header, written: header.size })
+    }
+
+    /// The parsed header.
+    #[inline]
+    pub fn header(&self) -> Header {
+        self.header
+    }
+
+    /// The raw data, including the header.
+    #[inline]
+    pub fn data(&self) -> &'a [u32] {
+        self.data
+    }
+
+    /// Bytes the BIOS writes when decompressing the data.
+    ///
+    /// LZ77 and run length data are written a block at a time, and
+    /// the BIOS only stops between blocks, so this can be up to 17
+    /// bytes more than the header's size for LZ77 data and 129 more for
+    /// run length data.  Destinations need room for all of it.
+    #[inline]
+    pub fn written_size(&self) -> usize {
+        self.written
+    }
+}
+
+fn check(source: &Compressed,
+         formats: &[Format],
+         unit_bytes: usize,
+         dest_bytes: usize) -> Result<usize, DecompressError> {
+    let header = source.header();
+    if !formats.contains(&header.format) {
+        return Err(DecompressError::WrongFormat(header.format));
+    }
+    let written = cmp::max(header.size, source.written);
+    let needed = (written + unit_bytes - 1) / unit_bytes * unit_bytes;
+    if dest_bytes < needed {
+        return Err(DecompressError::DestinationTooSmall {
+            needed: needed,
+            available: dest_bytes,
+        });
+    }
+    Ok(header.size)
+}
+
+macro_rules! swi_decompress {
+    ($name:ident, $source:expr, $dest:expr) => {
+        unsafe {
+            let len = mem::size_of_val(&*$dest);
+            let dest = slice::from_raw_parts_mut($dest.as_mut_ptr() as *mut u8, len);
+            ::sys::$name($source.data(), dest)?;
+        }
+    };
+}
+
+/// Decompresses LZ77 data into work RAM, returning the decompressed
+/// size in bytes.
+///
+/// This writes 8 bits at a time, so can't be used for VRAM.  `dest`
+/// needs room for `source.written_size()` bytes.
+///
+/// # Examples
+///
+/// ```
+/// extern crate gba_bios;
+/// extern crate gba_compress;
+///
+/// # fn main() {
+/// let data = b"abracadabra, abracadabra";
+/// let words = gba_compress::to_words(&gba_compress::lz77::compress(data).unwrap());
+/// let source = gba_bios::Compressed::new(&words).unwrap();
+/// let mut dest = [0u8; 24];
+/// assert_eq!(Ok(24), gba_bios::lz77_uncompress_wram(&source, &mut dest));
+/// assert_eq!(&data[..], &dest[..]);
+/// # }
+/// ```
+#[inline]
+pub fn lz77_uncompress_wram(source: &Compressed, dest: &mut [u8])
+                            -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::Lz77], 1, dest.len())?;
+    swi_decompress!(lz77_uncompress_wram, source, dest);
+    Ok(size)
+}
+
+/// Decompresses LZ77 data into VRAM, returning the decompressed
+/// size in bytes.
+///
+/// The data must not contain back-references with a distance of 1,
+/// as produced by VRAM-safe compressors.  `dest` needs room for
+/// `source.written_size()` bytes.
+#[inline]
+pub fn lz77_uncompress_vram(source: &Compressed, dest: &mut [u16])
+                            -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::Lz77], 2, dest.len() * 2)?;
+    swi_decompress!(lz77_uncompress_vram, source, dest);
+    Ok(size)
+}
+
+/// Decompresses Huffman data, returning the decompressed size in
+/// bytes.
+///
+/// This writes 32 bits at a time, so works for both work RAM and VRAM.
+///
+/// # Examples
+///
+/// ```
+/// extern crate gba_bios;
+/// extern crate gba_compress;
+///
+/// use gba_compress::huffman::{self, SymbolSize};
+///
+/// # fn main() {
+/// let data = b"mississippi, mississippi";
+/// let words = gba_compress::to_words(&huffman::compress(data, SymbolSize::Four).unwrap());
+/// let source = gba_bios::Compressed::new(&words).unwrap();
+/// let mut dest = [0u32; 6];
+/// assert_eq!(Ok(24), gba_bios::huffman_uncompress(&source, &mut dest));
+/// assert_eq!(gba_compress::to_words(data), dest.to_vec());
+/// # }
+/// ```
+#[inline]
+pub fn huffman_uncompress(source: &Compressed, dest: &mut [u32])
+                          -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::Huffman4, Format::Huffman8], 4, dest.len() * 4)?;
+    swi_decompress!(huffman_uncompress, source, dest);
+    Ok(size)
+}
+
+/// Decompresses run length encoded data into work RAM, returning the
+/// decompressed size in bytes.
+///
+/// This writes 8 bits at a time, so can't be used for VRAM.  `dest`
+/// needs room for `source.written_size()` bytes.
+#[inline]
+pub fn run_length_uncompress_wram(source: &Compressed, dest: &mut [u8])
+                                  -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::RunLength], 1, dest.len())?;
+    swi_decompress!(run_length_uncompress_wram, source, dest);
+    Ok(size)
+}
+
+/// Decompresses run length encoded data into VRAM, returning the
+/// decompressed size in bytes.
+///
+/// `dest` needs room for `source.written_size()` bytes.
+#[inline]
+pub fn run_length_uncompress_vram(source: &Compressed, dest: &mut [u16])
+                                  -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::RunLength], 2, dest.len() * 2)?;
+    swi_decompress!(run_length_uncompress_vram, source, dest);
+    Ok(size)
+}
+
+/// Undoes an 8-bit difference filter into work RAM, returning the
+/// unfiltered size in bytes.
+///
+/// This writes 8 bits at a time, so can't be used for VRAM.
+#[inline]
+pub fn diff8_unfilter_wram(source: &Compressed, dest: &mut [u8])
+                           -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::Diff8], 1, dest.len())?;
+    swi_decompress!(diff8_unfilter_wram, source, dest);
+    Ok(size)
+}
+
+/// Undoes an 8-bit difference filter into VRAM, returning the
+/// unfiltered size in bytes.
+#[inline]
+pub fn diff8_unfilter_vram(source: &Compressed, dest: &mut [u16])
+                           -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::Diff8], 2, dest.len() * 2)?;
+    swi_decompress!(diff8_unfilter_vram, source, dest);
+    Ok(size)
+}
+
+/// Undoes a 16-bit difference filter, returning the unfiltered size
+/// in bytes.
+#[inline]
+pub fn diff16_unfilter(source: &Compressed, dest: &mut [u16])
+                       -> Result<usize, DecompressError> {
+    let size = check(source, &[Format::Diff16], 2, dest.len() * 2)?;
+    swi_decompress!(diff16_unfilter, source, dest);
+    Ok(size)
+}
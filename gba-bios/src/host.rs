@@ -0,0 +1,188 @@
+//! Pure rust versions of the BIOS calls, for testing on other
+//! platforms.
+//!
+//! These follow the BIOS algorithms closely enough to give the same
+//! results, including the approximations in `arctan` and `arctan2`.
+//! Unlike the BIOS, decompression stops at the end of the data and
+//! the destination, so bad data gives an error rather than overwriting
+//! memory.
+
+use core::cmp;
+use decompress::{DecompressError, Header};
+use stream::{self, Output};
+
+pub fn div_modulo_absdiv(num: i32, denom: i32) -> (i32, i32, i32) {
+    let div = num.wrapping_div(denom);
+    (div, num.wrapping_rem(denom), div.wrapping_abs())
+}
+
+pub fn sqrt(x: u32) -> u16 {
+    let mut remainder = x;
+    let mut root = 0u32;
+    let mut bit = 1u32 << 30;
+    while bit > remainder {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root as u16
+}
+
+// The BIOS approximates arctan with a polynomial in 32-bit arithmetic.
+fn arctan_poly(i: i32) -> i32 {
+    let a = -(i.wrapping_mul(i) >> 14);
+    let mut b = (0xA9i32.wrapping_mul(a) >> 14) + 0x390;
+    for &c in [0x91C, 0xFB6, 0x16AA, 0x2081, 0x3651, 0xA2F9].iter() {
+        b = (b.wrapping_mul(a) >> 14) + c;
+    }
+    i.wrapping_mul(b) >> 16
+}
+
+pub fn arctan(x: i16) -> i16 {
+    arctan_poly(x as i32) as i16
+}
+
+pub fn arctan2(x: i16, y: i16) -> u16 {
+    let (x, y) = (x as i32, y as i32);
+    let angle = if y == 0 {
+        if x >= 0 { 0 } else { 0x8000 }
+    } else if x == 0 {
+        if y >= 0 { 0x4000 } else { 0xC000 }
+    } else if y >= 0 {
+        if x >= 0 && x >= y {
+            arctan_poly((y << 14) / x)
+        } else if x < 0 && -x >= y {
+            arctan_poly((y << 14) / x) + 0x8000
+        } else {
+            0x4000 - arctan_poly((x << 14) / y)
+        }
+    } else if x <= 0 && -x > -y {
+        arctan_poly((y << 14) / x) + 0x8000
+    } else if x > 0 && x >= -y {
+        arctan_poly((y << 14) / x) + 0x10000
+    } else {
+        0xC000 - arctan_poly((x << 14) / y)
+    };
+    angle as u16
+}
+
+const FILL: u32 = 1 << 24;
+const WORDS: u32 = 1 << 26;
+const COUNT: u32 = (1 << 21) - 1;
+
+pub unsafe fn cpu_set(source: *const u8, dest: *mut u8, control: u32) {
+    let count = (control & COUNT) as usize;
+    let fill = control & FILL != 0;
+    if control & WORDS != 0 {
+        let (source, dest) = (source as *const u32, dest as *mut u32);
+        for i in 0..count {
+            let from = if fill { 0 } else { i };
+            *dest.offset(i as isize) = *source.offset(from as isize);
+        }
+    } else {
+        let (source, dest) = (source as *const u16, dest as *mut u16);
+        for i in 0..count {
+            let from = if fill { 0 } else { i };
+            *dest.offset(i as isize) = *source.offset(from as isize);
+        }
+    }
+}
+
+pub unsafe fn cpu_fast_set(source: *const u32, dest: *mut u32, control: u32) {
+    // The BIOS always transfers blocks of 8 words.
+    let count = ((control & COUNT) as usize + 7) & !7;
+    let fill = control & FILL != 0;
+    for i in 0..count {
+        let from = if fill { 0 } else { i };
+        *dest.offset(i as isize) = *source.offset(from as isize);
+    }
+}
+
+// Writes decompressed bytes one unit at a time, as the BIOS does,
+// dropping any past `limit`.
+//
+// In VRAM mode bytes are buffered into halfwords, so until the second
+// byte of a halfword arrives the first is not yet in memory.
+struct Dest<'a> {
+    data: &'a mut [u8],
+    limit: usize,
+    pos: usize,
+    unit: usize,
+    pending: u8,
+}
+
+impl<'a> Output for Dest<'a> {
+    fn len(&self) -> usize {
+        self.pos
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.pos < self.limit {
+            if self.unit == 1 {
+                self.data[self.pos] = byte;
+            } else if self.pos & 1 == 0 {
+                self.pending = byte;
+            } else {
+                self.data[self.pos - 1] = self.pending;
+                self.data[self.pos] = byte;
+            }
+        }
+        self.pos += 1;
+    }
+
+    fn back(&self, distance: usize) -> u8 {
+        self.data.get(self.pos - distance).cloned().unwrap_or(0)
+    }
+}
+
+// Decompresses `source` into `dest`, stopping at the end of either.
+fn decompress(source: &[u32], dest: &mut [u8], unit: usize) -> Result<(), DecompressError> {
+    let header = match source.first() {
+        Some(&word) => Header::parse(word)?,
+        None => return Err(DecompressError::MissingHeader),
+    };
+    let limit = cmp::min(header.size, dest.len());
+    let mut out = Dest { data: dest, limit: limit, pos: 0, unit: unit, pending: 0 };
+    stream::walk(source, header, &mut out)
+}
+
+pub fn lz77_uncompress_wram(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+    decompress(source, dest, 1)
+}
+
+pub fn lz77_uncompress_vram(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+    decompress(source, dest, 2)
+}
+
+pub fn huffman_uncompress(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+    decompress(source, dest, 1)
+}
+
+pub fn run_length_uncompress_wram(source: &[u32], dest: &mut [u8])
+                                  -> Result<(), DecompressError> {
+    decompress(source, dest, 1)
+}
+
+pub fn run_length_uncompress_vram(source: &[u32], dest: &mut [u8])
+                                  -> Result<(), DecompressError> {
+    decompress(source, dest, 2)
+}
+
+pub fn diff8_unfilter_wram(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+    decompress(source, dest, 1)
+}
+
+pub fn diff8_unfilter_vram(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+    decompress(source, dest, 2)
+}
+
+pub fn diff16_unfilter(source: &[u32], dest: &mut [u8]) -> Result<(), DecompressError> {
+    decompress(source, dest, 1)
+}
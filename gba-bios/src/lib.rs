@@ -1,19 +1,74 @@
-#![feature(asm)]
-#![no_std]
+//! Bindings to the game boy advance BIOS.
+//!
+//! When not targeting the game boy advance, the arithmetic, memory
+//! and decompression functions fall back to pure rust versions that
+//! give the same results, so code using them can be tested on the
+//! host.  Functions that only make sense on the hardware, like
+//! waiting for interrupts, are not available there.
+//!
+//! The `swi` instructions are encoded for Thumb state, as for the
+//! `thumbv4t-none-eabi` target.  Targets that generate ARM code, like
+//! `armv4t-none-eabi`, need the `arm-state` feature instead.
 
-#[cfg(not(target_arch = "arm"))]
-compile_error!("This crate is specific to the gameboy advance");
+#![cfg_attr(target_arch = "arm", feature(asm))]
+#![no_std]
 
+#[cfg(target_arch = "arm")]
+#[macro_use]
+extern crate bitflags;
 extern crate gba_hw;
 
+#[macro_use]
+mod swi;
+pub use swi::Swi;
+
+#[cfg(target_arch = "arm")]
+#[path = "arm.rs"]
+mod sys;
+
+#[cfg(not(target_arch = "arm"))]
+#[path = "host.rs"]
+mod sys;
+
 mod arith;
 pub use arith::*;
 
+#[cfg(target_arch = "arm")]
 mod reset;
+#[cfg(target_arch = "arm")]
 pub use reset::*;
 
 mod mem;
 pub use mem::*;
 
+#[cfg(target_arch = "arm")]
 mod wait;
-pub use wait::*;
\ No newline at end of file
+#[cfg(target_arch = "arm")]
+pub use wait::*;
+
+mod decompress;
+pub use decompress::*;
+mod stream;
+
+#[cfg(target_arch = "arm")]
+mod affine;
+#[cfg(target_arch = "arm")]
+pub use affine::*;
+
+#[cfg(target_arch = "arm")]
+mod unpack;
+#[cfg(target_arch = "arm")]
+pub use unpack::*;
+
+#[cfg(target_arch = "arm")]
+mod sound;
+#[cfg(target_arch = "arm")]
+pub use sound::*;
+
+mod link;
+pub use link::*;
+
+#[cfg(target_arch = "arm")]
+mod system;
+#[cfg(target_arch = "arm")]
+pub use system::*;
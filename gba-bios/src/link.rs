@@ -0,0 +1,308 @@
+//! Sending a multiboot image to other game boy advances over the link
+//! cable.
+//!
+//! Before the BIOS `MultiBoot` call can send the image, the master has
+//! to find the clients, send them the cartridge header, and swap the
+//! random data that seeds the encryption.  That part of the protocol is
+//! written against a `Transport`, so it can be tested on the host
+//! against a simulated client; `send` then runs it over the serial port
+//! and hands over to the BIOS for the encrypted transfer and CRC.
+//!
+//! # Examples
+//!
+//! A simulated client in multi-player mode, as client 1:
+//!
+//! ```
+//! use gba_bios::{handshake, Progress, Transport};
+//!
+//! struct Client {
+//!     header: Vec<u16>,
+//!     // header halfwords received, once the master has found us
+//!     received: Option<usize>,
+//!     probes: usize,
+//! }
+//!
+//! impl Transport for Client {
+//!     fn exchange(&mut self, data: u16) -> Option<[u16; 3]> {
+//!         let reply = match (self.received, data >> 8) {
+//!             // needs a few probes before it's ready
+//!             (None, 0x62) if self.probes < 3 => { self.probes += 1; 0xFFFF }
+//!             (None, 0x61) => { self.received = Some(0); 0x7202 }
+//!             (None, _) => 0x7202,
+//!             (Some(n), _) if n < 0x60 => {
+//!                 assert_eq!(self.header[n], data);
+//!                 self.received = Some(n + 1);
+//!                 ((0x60 - n as u16) << 8) | 0x02
+//!             }
+//!             (Some(0x60), 0x62) => { self.received = Some(0x61); 0x0002 }
+//!             (_, 0x62) => 0x7202,
+//!             (_, 0x63) => 0x7342,
+//!             _ => 0x7300,
+//!         };
+//!         Some([reply, 0xFFFF, 0xFFFF])
+//!     }
+//!
+//!     fn delay(&mut self) {}
+//! }
+//!
+//! let image: Vec<u8> = (0..0x1C0).map(|i| i as u8).collect();
+//! let mut client = Client {
+//!     header: image[..0xC0].chunks(2).map(|b| b[0] as u16 | (b[1] as u16) << 8).collect(),
+//!     received: None,
+//!     probes: 0,
+//! };
+//!
+//! let mut sent = 0;
+//! let result = handshake(&mut client, &image, 0xC1, |progress| {
+//!     if let Progress::Header(n) = progress {
+//!         sent = n;
+//!     }
+//! }).unwrap();
+//! assert_eq!(0x60, sent);
+//! assert_eq!(0x02, result.client_bit);
+//! assert_eq!([0x42, 0xFF, 0xFF], result.client_data);
+//! // 0x11 + 0x42 + 0xFF + 0xFF
+//! assert_eq!(0x51, result.handshake_data);
+//! ```
+
+#[cfg(target_arch = "arm")]
+use system::{multiboot, MultiBootMode, MultiBootParam};
+
+/// Size of the cartridge header sent during the handshake.
+const HEADER_SIZE: usize = 0xC0;
+
+/// Probe rounds without an answer before giving up, each followed by
+/// a `delay`, so about four seconds.
+const PROBE_ROUNDS: usize = 64;
+
+/// Answers in a row needed from the same clients before going on.
+const PROBE_CONFIRMATIONS: usize = 15;
+
+/// Rounds to wait for the clients to send their random data.
+const SEED_ROUNDS: usize = 64;
+
+/// Palette and speed of the boot logo animation that most games use.
+pub const DEFAULT_PALETTE_DATA: u8 = 0xC1;
+
+/// The serial link to the clients.
+pub trait Transport {
+    /// Sends `data` to every client, returning what clients 1-3 sent
+    /// back at the same time, or `None` if the transfer failed.
+    /// Missing clients answer `0xFFFF`.
+    fn exchange(&mut self, data: u16) -> Option<[u16; 3]>;
+
+    /// Waits about 1/16 of a second, to give the clients time to
+    /// catch up.
+    fn delay(&mut self);
+}
+
+/// How far sending has got, for showing progress.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Progress {
+    /// Found clients, with bits 1-3 set for each one.
+    Found(u8),
+
+    /// Sent this many of the 0x60 header halfwords.
+    Header(usize),
+
+    /// Swapped the encryption seed with the clients.
+    Seeded,
+
+    /// The BIOS is sending the rest of the image.
+    Transferring,
+}
+
+/// Errors from sending a multiboot image.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SendError {
+    /// The image isn't between 0x1C0 and 0x40000 bytes, or the part
+    /// after the header isn't a multiple of 16 bytes.
+    ImageSize(usize),
+
+    /// No client answered.
+    NoClients,
+
+    /// A serial transfer failed.
+    Transfer,
+
+    /// A client gave the wrong answer, or stopped answering.
+    Client {
+        /// The client, 1-3.
+        client: u8,
+        /// What it sent.
+        reply: u16,
+    },
+
+    /// The clients never sent their encryption seed.
+    Seed,
+
+    /// The BIOS transfer failed.
+    Failed,
+}
+
+/// The result of a successful handshake, to be passed on to the BIOS.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Handshake {
+    /// Bits 1-3 set for each client.
+    pub client_bit: u8,
+
+    /// Random data sent by each client, `0xFF` for missing ones.
+    pub client_data: [u8; 3],
+
+    /// Palette and speed of the boot logo animation.
+    pub palette_data: u8,
+
+    /// 0x11 plus the sum of the client data.
+    pub handshake_data: u8,
+}
+
+/// What the client sent back in a 32-bit normal mode transfer, as
+/// `Transport::exchange` returns it.  The client answers as client 1,
+/// in the high halfword.
+pub fn normal_replies(received: u32) -> [u16; 3] {
+    [(received >> 16) as u16, 0xFFFF, 0xFFFF]
+}
+
+fn exchange<T: Transport>(transport: &mut T, data: u16) -> Result<[u16; 3], SendError> {
+    transport.exchange(data).ok_or(SendError::Transfer)
+}
+
+// Checks that every client in `clients` answered `expected(bit)`.
+fn check<F: Fn(u16) -> u16>(replies: [u16; 3], clients: u8, expected: F) -> Result<(), SendError> {
+    for client in 1..4 {
+        let bit = 1 << client;
+        let reply = replies[client - 1];
+        if clients & bit != 0 && reply != expected(bit as u16) {
+            return Err(SendError::Client { client: client as u8, reply });
+        }
+    }
+    Ok(())
+}
+
+// The clients answering a probe, with bits 1-3 set for each.
+fn answered(replies: [u16; 3]) -> u8 {
+    (1..4).filter(|&client| replies[client - 1] == 0x7200 | 1 << client)
+        .fold(0, |clients, client| clients | 1 << client)
+}
+
+fn probe<T: Transport>(transport: &mut T) -> Result<u8, SendError> {
+    let mut clients = 0;
+    let mut confirmations = 0;
+    let mut rounds = 0;
+    while confirmations < PROBE_CONFIRMATIONS {
+        let found = match transport.exchange(0x6200) {
+            Some(replies) => answered(replies),
+            None => 0,
+        };
+        if found != 0 && found == clients {
+            confirmations += 1;
+        } else {
+            clients = found;
+            confirmations = (found != 0) as usize;
+            rounds += 1;
+            if rounds == PROBE_ROUNDS {
+                return Err(SendError::NoClients);
+            }
+            transport.delay();
+        }
+    }
+    Ok(clients)
+}
+
+/// Finds the clients, sends them the header of `image`, and swaps the
+/// encryption seed with them, calling `progress` along the way.
+///
+/// This is the part of sending before the BIOS takes over, which
+/// `send` does next.
+pub fn handshake<T, P>(transport: &mut T, image: &[u8], palette_data: u8, mut progress: P)
+                       -> Result<Handshake, SendError>
+    where T: Transport, P: FnMut(Progress)
+{
+    if image.len() < HEADER_SIZE {
+        return Err(SendError::ImageSize(image.len()));
+    }
+
+    let clients = probe(transport)?;
+    progress(Progress::Found(clients));
+    let replies = exchange(transport, 0x6100 | clients as u16)?;
+    check(replies, clients, |bit| 0x7200 | bit)?;
+
+    for (i, pair) in image[..HEADER_SIZE].chunks(2).enumerate() {
+        let replies = exchange(transport, pair[0] as u16 | (pair[1] as u16) << 8)?;
+        check(replies, clients, |bit| ((0x60 - i as u16) << 8) | bit)?;
+        progress(Progress::Header(i + 1));
+    }
+
+    let replies = exchange(transport, 0x6200)?;
+    check(replies, clients, |bit| bit)?;
+    let replies = exchange(transport, 0x6200 | clients as u16)?;
+    check(replies, clients, |bit| 0x7200 | bit)?;
+
+    // The clients answer 0x720x until they've picked their random
+    // data, then 0x73cc.
+    let mut client_data = [0xFF; 3];
+    let mut seeded = false;
+    for _ in 0..SEED_ROUNDS {
+        let replies = exchange(transport, 0x6300 | palette_data as u16)?;
+        let ready = (1..4).all(|client| clients & 1 << client == 0 ||
+                               replies[client - 1] >> 8 == 0x73);
+        if ready {
+            for client in 1..4 {
+                if clients & 1 << client != 0 {
+                    client_data[client - 1] = replies[client - 1] as u8;
+                }
+            }
+            seeded = true;
+            break;
+        }
+        check(replies, clients, |bit| 0x7200 | bit)?;
+    }
+    if !seeded {
+        return Err(SendError::Seed);
+    }
+
+    let handshake_data = client_data.iter().fold(0x11u8, |sum, &data| sum.wrapping_add(data));
+    let replies = exchange(transport, 0x6400 | handshake_data as u16)?;
+    for client in 1..4 {
+        let reply = replies[client - 1];
+        if clients & 1 << client != 0 && reply >> 8 != 0x73 {
+            return Err(SendError::Client { client: client as u8, reply });
+        }
+    }
+    progress(Progress::Seeded);
+
+    Ok(Handshake {
+        client_bit: clients,
+        client_data,
+        palette_data,
+        handshake_data,
+    })
+}
+
+/// Sends `image`, a whole multiboot game including its cartridge
+/// header, to the clients on `transport`, calling `progress` along the
+/// way.
+///
+/// In multi-player mode there can be up to three clients; in normal
+/// mode there is one, which answers as client 1.  The BIOS transfer
+/// at the end takes a few seconds, with interrupts disabled.
+#[cfg(target_arch = "arm")]
+pub fn send<T, P>(transport: &mut T,
+                  image: &[u8],
+                  mode: MultiBootMode,
+                  palette_data: u8,
+                  mut progress: P)
+                  -> Result<(), SendError>
+    where T: Transport, P: FnMut(Progress)
+{
+    let mut param = MultiBootParam::new(image).map_err(|_| SendError::ImageSize(image.len()))?;
+    let handshake = handshake(transport, image, palette_data, &mut progress)?;
+    param.client_bit = handshake.client_bit;
+    param.client_data = handshake.client_data;
+    param.palette_data = handshake.palette_data;
+    param.handshake_data = handshake.handshake_data;
+
+    transport.delay();
+    progress(Progress::Transferring);
+    multiboot(&mut param, mode).map_err(|_| SendError::Failed)
+}
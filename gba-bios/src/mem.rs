@@ -1,62 +1,151 @@
-/// Quickly copies memory, subject to a few conditions.
+/// `cpu_set` control flag to fill the destination with the first
+/// unit of the source, rather than copying.
+pub const CPU_SET_FILL: u32 = 1 << 24;
+
+/// `cpu_set` control flag to transfer 32 bits at a time, rather
+/// than 16.
+pub const CPU_SET_32BIT: u32 = 1 << 26;
+
+/// Largest number of units a single `cpu_set` or `cpu_fast_set`
+/// call can transfer.
+pub const CPU_SET_MAX_COUNT: usize = (1 << 21) - 1;
+
+/// Copies or fills memory with the BIOS `CpuSet` call.
 ///
 /// # Arguments
 ///
-/// * `source` 4-byte aligned slice to copy from
-/// * `dest` 4-byte aligned slice to copy to
+/// * `source` data to copy, or a pointer to the value to fill with
+/// * `dest` destination
+/// * `control` unit count in bits 0-20, along with `CPU_SET_FILL`
+///   and `CPU_SET_32BIT`
 ///
-/// # Caveats
+/// # Safety
 ///
-/// Note that `source` and `dest` must have the same length,
-/// and that this length *must* be a multiple of 32 bytes.
+/// Both pointers must be aligned to the unit size, and valid for the
+/// whole transfer.
 #[inline(always)]
-pub fn fast_copy(source: &[u8], dest: &mut [u8]) {
-    let src_ptr = source.as_ptr();
-    let dst_ptr = dest.as_mut_ptr();
-    let len_bytes = source.len();
+pub unsafe fn cpu_set(source: *const u8, dest: *mut u8, control: u32) {
+    ::sys::cpu_set(source, dest, control)
+}
 
-    // Check pre-conditions in debug mode.
-    debug_assert!(src_ptr as usize & 0x3 == 0);
-    debug_assert!(dst_ptr as usize & 0x3 == 0);
-    debug_assert!(source.len() == dest.len());
-    debug_assert!(len_bytes & 31 == 0);
+/// Copies or fills memory 32 bytes at a time with the BIOS
+/// `CpuFastSet` call.
+///
+/// # Arguments
+///
+/// * `source` data to copy, or a pointer to the value to fill with
+/// * `dest` destination
+/// * `control` word count in bits 0-20, rounded up to a multiple of
+///   8 by the BIOS, along with `CPU_SET_FILL`
+///
+/// # Safety
+///
+/// Both pointers must be valid for the rounded up transfer.
+#[inline(always)]
+pub unsafe fn cpu_fast_set(source: *const u32, dest: *mut u32, control: u32) {
+    ::sys::cpu_fast_set(source, dest, control)
+}
 
-    let len_words = len_bytes >> 2;
+#[inline(always)]
+fn count(len: usize) -> u32 {
+    assert!(len <= CPU_SET_MAX_COUNT);
+    len as u32
+}
+
+/// Copies halfwords, which is safe for VRAM, palette RAM and OAM.
+///
+/// # Panics
+///
+/// If `source` and `dest` have different lengths.
+#[inline]
+pub fn copy16(source: &[u16], dest: &mut [u16]) {
+    assert_eq!(source.len(), dest.len());
+    unsafe {
+        cpu_set(source.as_ptr() as *const u8, dest.as_mut_ptr() as *mut u8,
+                count(dest.len()));
+    }
+}
 
+/// Fills halfwords, which is safe for VRAM, palette RAM and OAM.
+#[inline]
+pub fn set16(val: u16, dest: &mut [u16]) {
     unsafe {
-        asm!("swi 0x0c"
-             :
-             : "{r0}"(src_ptr)
-             , "{r1}"(dst_ptr)
-             , "{r2}"(len_words)
-        );
+        cpu_set(&val as *const u16 as *const u8, dest.as_mut_ptr() as *mut u8,
+                count(dest.len()) | CPU_SET_FILL);
     }
 }
 
-/// Quickly fills memory, subject to a few conditions.
+/// Copies words.
 ///
-/// # Arguments
+/// # Panics
 ///
-/// * `val` value to fill memory with.
-/// * `dest` 4-byte aligned slice to fill, must be a multiple of
-///   32 bytes.
-#[inline(always)]
-pub fn fast_set(val: u32, dest: &mut [u8]) {
-    let dst_ptr = dest.as_mut_ptr();
-    let len_bytes = dest.len();
+/// If `source` and `dest` have different lengths.
+#[inline]
+pub fn copy32(source: &[u32], dest: &mut [u32]) {
+    assert_eq!(source.len(), dest.len());
+    unsafe {
+        cpu_set(source.as_ptr() as *const u8, dest.as_mut_ptr() as *mut u8,
+                count(dest.len()) | CPU_SET_32BIT);
+    }
+}
 
-    // Check pre-conditions in debug mode.
-    debug_assert!(dst_ptr as usize & 0x3 == 0);
-    debug_assert!(len_bytes & 31 == 0);
+/// Fills words.
+#[inline]
+pub fn set32(val: u32, dest: &mut [u32]) {
+    unsafe {
+        cpu_set(&val as *const u32 as *const u8, dest.as_mut_ptr() as *mut u8,
+                count(dest.len()) | CPU_SET_32BIT | CPU_SET_FILL);
+    }
+}
 
-    let len_words = len_bytes >> 2;
+/// Quickly copies words.
+///
+/// The bulk of the copy is done 8 words at a time with `CpuFastSet`,
+/// and any remainder with `CpuSet`.
+///
+/// # Panics
+///
+/// If `source` and `dest` have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// use gba_bios::fast_copy;
+///
+/// let source = [7u32; 11];
+/// let mut dest = [0u32; 11];
+/// fast_copy(&source, &mut dest);
+/// assert_eq!(source, dest);
+/// ```
+#[inline]
+pub fn fast_copy(source: &[u32], dest: &mut [u32]) {
+    assert_eq!(source.len(), dest.len());
+    let bulk = dest.len() & !7;
+    unsafe {
+        cpu_fast_set(source.as_ptr(), dest.as_mut_ptr(), count(bulk));
+    }
+    copy32(&source[bulk..], &mut dest[bulk..]);
+}
 
+/// Quickly fills words.
+///
+/// The bulk of the fill is done 8 words at a time with `CpuFastSet`,
+/// and any remainder with `CpuSet`.
+///
+/// # Examples
+///
+/// ```
+/// use gba_bios::fast_set;
+///
+/// let mut dest = [0u32; 11];
+/// fast_set(0xDEADBEEF, &mut dest);
+/// assert_eq!([0xDEADBEEF; 11], dest);
+/// ```
+#[inline]
+pub fn fast_set(val: u32, dest: &mut [u32]) {
+    let bulk = dest.len() & !7;
     unsafe {
-        asm!("swi 0x0c"
-             :
-             : "{r0}"(val)
-             , "{r1}"(dst_ptr)
-             , "{r2}"(len_words)
-        );
+        cpu_fast_set(&val, dest.as_mut_ptr(), count(bulk) | CPU_SET_FILL);
     }
-}
\ No newline at end of file
+    set32(val, &mut dest[bulk..]);
+}
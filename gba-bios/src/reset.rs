@@ -1,5 +1,48 @@
+bitflags! {
+    /// Memory regions and registers cleared by `register_ram_reset`.
+    pub flags ResetFlags: u8 {
+        /// All 256KiB of external work RAM.
+        const EWRAM     = 1 << 0,
+        /// Internal work RAM, except the last 0x200 bytes used by the
+        /// stacks and BIOS.
+        const IWRAM     = 1 << 1,
+        const PALETTE   = 1 << 2,
+        const VRAM      = 1 << 3,
+        const OAM       = 1 << 4,
+        /// Serial registers, switching to general purpose mode.
+        const SIO       = 1 << 5,
+        const SOUND     = 1 << 6,
+        /// All other registers.
+        const REGISTERS = 1 << 7,
+    }
+}
+
+/// Restarts the game from the start of ROM, or of EWRAM for
+/// multiboot games.
 #[inline(always)]
 pub fn soft_reset() -> ! {
-    unsafe { asm!("swi 0"); }
+    unsafe { asm!(swi!(SoftReset)); }
     unreachable!();
-}
\ No newline at end of file
+}
+
+/// Clears the given memory regions and registers.
+///
+/// `DISPCNT` is always reset to forced blank, whatever the flags.
+///
+/// This function is unsafe because clearing work RAM will clear
+/// statics and code loaded there, such as the interrupt handler.
+#[inline(always)]
+pub unsafe fn register_ram_reset(flags: ResetFlags) {
+    asm!(swi!(RegisterRamReset)
+         :
+         : "{r0}"(flags.bits() as u32)
+         : "r0", "r1", "r2", "r3", "memory"
+         : "volatile");
+}
+
+/// Reboots the system, including the boot logo.
+#[inline(always)]
+pub fn hard_reset() -> ! {
+    unsafe { asm!(swi!(HardReset)); }
+    unreachable!();
+}
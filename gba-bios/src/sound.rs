@@ -0,0 +1,70 @@
+/// The header of a sampled instrument, as used by `midi_key_to_freq`.
+///
+/// The signed 8-bit samples follow directly after the header.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct WaveData {
+    /// Sample type, zero for uncompressed 8-bit samples
+    pub kind: u16,
+
+    /// Bit 14 is set if the sample loops
+    pub status: u16,
+
+    /// Sample rate of the recorded note, times 1024
+    pub frequency: u32,
+
+    /// Index of the sample to loop back to
+    pub loop_start: u32,
+
+    /// Number of samples
+    pub size: u32,
+}
+
+/// Highest MIDI key `midi_key_to_freq` accepts.
+pub const MIDI_KEY_MAX: u8 = 178;
+
+/// Calculates the sample rate to play `wave` at for a MIDI key.
+///
+/// # Arguments
+///
+/// * `wave` instrument, recorded at middle C (key 60)
+/// * `key` MIDI key, up to `MIDI_KEY_MAX`
+/// * `fine_adjust` extra pitch, in 256ths of a semitone
+///
+/// Returns the sample rate, times 1024.
+///
+/// # Panics
+///
+/// If `key` is higher than `MIDI_KEY_MAX`.
+#[inline]
+pub fn midi_key_to_freq(wave: &WaveData, key: u8, fine_adjust: u8) -> u32 {
+    assert!(key <= MIDI_KEY_MAX);
+    let out: u32;
+    unsafe {
+        asm!(swi!(MidiKey2Freq)
+             : "={r0}"(out)
+             : "{r0}"(wave as *const WaveData)
+             , "{r1}"(key as u32)
+             , "{r2}"(fine_adjust as u32)
+             : "r1", "r2", "r3");
+    }
+    out
+}
+
+/// Gradually moves the sound bias level, to avoid a click when
+/// starting or stopping sound.
+///
+/// # Arguments
+///
+/// * `on` if true, raises the bias to the normal level of 0x200,
+///   otherwise lowers it to zero
+#[inline]
+pub fn sound_bias(on: bool) {
+    unsafe {
+        asm!(swi!(SoundBias)
+             :
+             : "{r0}"(on as u32)
+             : "r0", "r1", "r2", "r3"
+             : "volatile");
+    }
+}
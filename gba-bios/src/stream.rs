@@ -0,0 +1,211 @@
+//! Walks compressed data the way the BIOS decompresses it.
+//!
+//! `Compressed::new` walks the data without writing anything, to check
+//! the BIOS will stay inside it and find how far the BIOS will write.
+//! Off the game boy advance, the same walks do the decompressing.
+
+use decompress::{DecompressError, Format, Header};
+
+/// Reads the compressed data a byte at a time, failing at the end
+/// rather than reading past it.
+struct Input<'a> {
+    data: &'a [u32],
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    /// Starts reading just after the header.
+    fn new(data: &'a [u32]) -> Input<'a> {
+        Input { data: data, pos: 4 }
+    }
+
+    /// The byte at `pos`, counting from the start of the header.
+    fn peek(&self, pos: usize) -> Result<u8, DecompressError> {
+        match self.data.get(pos / 4) {
+            Some(&word) => Ok((word >> (pos % 4 * 8)) as u8),
+            None => Err(DecompressError::Truncated),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, DecompressError> {
+        let byte = self.peek(self.pos)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn word(&mut self) -> Result<u32, DecompressError> {
+        let mut word = 0;
+        for i in 0..4 {
+            word |= (self.byte()? as u32) << (i * 8);
+        }
+        Ok(word)
+    }
+}
+
+/// Where decompressed bytes go.
+pub trait Output {
+    /// Bytes pushed so far.
+    fn len(&self) -> usize;
+
+    fn push(&mut self, byte: u8);
+
+    /// The byte pushed `distance` bytes ago.  `distance` is never more
+    /// than `len`.
+    fn back(&self, distance: usize) -> u8;
+}
+
+/// Counts the bytes the BIOS would write, without keeping them.
+pub struct Count {
+    pub len: usize,
+}
+
+impl Output for Count {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn push(&mut self, _byte: u8) {
+        self.len += 1;
+    }
+
+    fn back(&self, _distance: usize) -> u8 {
+        0
+    }
+}
+
+/// Decompresses `data`, whose header is `header`, into `out`.
+///
+/// Like the BIOS, LZ77 and run length data are only checked against
+/// the size between blocks, so a block that crosses the size is pushed
+/// whole.
+pub fn walk<O: Output>(data: &[u32], header: Header, out: &mut O)
+                       -> Result<(), DecompressError> {
+    match header.format {
+        Format::Lz77 => lz77(data, header.size, out),
+        Format::Huffman4 => huffman(data, header.size, 4, out),
+        Format::Huffman8 => huffman(data, header.size, 8, out),
+        Format::RunLength => run_length(data, header.size, out),
+        Format::Diff8 => diff8(data, header.size, out),
+        Format::Diff16 => diff16(data, header.size, out),
+    }
+}
+
+fn lz77<O: Output>(data: &[u32], size: usize, out: &mut O) -> Result<(), DecompressError> {
+    let mut input = Input::new(data);
+    while out.len() < size {
+        let flags = input.byte()?;
+        for block in 0..8 {
+            if out.len() >= size {
+                break;
+            }
+            if flags & (0x80 >> block) == 0 {
+                let byte = input.byte()?;
+                out.push(byte);
+                continue;
+            }
+            let encoded = (input.byte()? as usize) << 8 | input.byte()? as usize;
+            let distance = (encoded & 0xFFF) + 1;
+            if distance > out.len() {
+                return Err(DecompressError::InvalidReference);
+            }
+            for _ in 0..((encoded >> 12) + 3) {
+                let byte = out.back(distance);
+                out.push(byte);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn huffman<O: Output>(data: &[u32], size: usize, bits: usize, out: &mut O)
+                      -> Result<(), DecompressError> {
+    let mut input = Input::new(data);
+    // the tree's size in halfwords, less one, comes before its root,
+    // and the bitstream follows it
+    let tree_end = 4 + (input.byte()? as usize + 1) * 2;
+    let root = input.pos;
+    input.pos = tree_end;
+
+    let mut node_pos = root;
+    let mut node = input.peek(root)?;
+    let mut block = 0u32;
+    let mut block_bits = 0;
+    while out.len() < size {
+        let mut stream = input.word()?;
+        for _ in 0..32 {
+            if out.len() >= size {
+                break;
+            }
+            let next = (node_pos & !1) + (node & 0x3F) as usize * 2 + 2;
+            let (child, is_leaf) = if stream & 0x8000_0000 != 0 {
+                (next + 1, node & 0x40 != 0)
+            } else {
+                (next, node & 0x80 != 0)
+            };
+            if child >= tree_end {
+                return Err(DecompressError::InvalidTree);
+            }
+            stream <<= 1;
+            let value = input.peek(child)?;
+            if !is_leaf {
+                node_pos = child;
+                node = value;
+                continue;
+            }
+
+            block |= (value as u32 & ((1 << bits) - 1)) << block_bits;
+            block_bits += bits;
+            node_pos = root;
+            node = input.peek(root)?;
+            if block_bits == 32 {
+                for i in 0..4 {
+                    out.push((block >> (i * 8)) as u8);
+                }
+                block = 0;
+                block_bits = 0;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_length<O: Output>(data: &[u32], size: usize, out: &mut O)
+                         -> Result<(), DecompressError> {
+    let mut input = Input::new(data);
+    while out.len() < size {
+        let flag = input.byte()?;
+        if flag & 0x80 == 0 {
+            for _ in 0..((flag & 0x7F) + 1) {
+                let byte = input.byte()?;
+                out.push(byte);
+            }
+        } else {
+            let byte = input.byte()?;
+            for _ in 0..((flag & 0x7F) + 3) {
+                out.push(byte);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn diff8<O: Output>(data: &[u32], size: usize, out: &mut O) -> Result<(), DecompressError> {
+    let mut input = Input::new(data);
+    let mut value = 0u8;
+    while out.len() < size {
+        value = value.wrapping_add(input.byte()?);
+        out.push(value);
+    }
+    Ok(())
+}
+
+fn diff16<O: Output>(data: &[u32], size: usize, out: &mut O) -> Result<(), DecompressError> {
+    let mut input = Input::new(data);
+    let mut value = 0u16;
+    for _ in 0..(size / 2) {
+        value = value.wrapping_add(input.byte()? as u16 | (input.byte()? as u16) << 8);
+        out.push(value as u8);
+        out.push((value >> 8) as u8);
+    }
+    Ok(())
+}
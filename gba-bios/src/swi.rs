@@ -0,0 +1,150 @@
+// Defines `Swi` along with `swi_number!`, which gives the same
+// numbers as string literals for use in `asm!`, so there is only one
+// place a call number can be wrong.
+macro_rules! swi_table {
+    ($($(#[$attr:meta])* $name:ident = $number:tt,)*) => {
+        /// BIOS calls, numbered as in GBATEK.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use gba_bios::Swi;
+        ///
+        /// assert_eq!(0x25, Swi::MultiBoot.number());
+        /// ```
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        pub enum Swi {
+            $($(#[$attr])* $name = $number,)*
+        }
+
+        impl Swi {
+            /// The call number, as used in the `swi` instruction.
+            #[inline]
+            pub fn number(&self) -> u8 {
+                *self as u8
+            }
+
+            /// Every call in the table.
+            pub fn all() -> &'static [Swi] {
+                &[$(Swi::$name,)*]
+            }
+        }
+
+        macro_rules! swi_number {
+            $(($name) => (stringify!($number));)*
+        }
+
+        // Every call's instruction in both states, for the tests.
+        #[cfg(test)]
+        const INSTRUCTIONS: &[(Swi, &str, &str)] = &[
+            $((Swi::$name, swi_instruction!(thumb, $name), swi_instruction!(arm, $name)),)*
+        ];
+    };
+}
+
+// The `swi` instruction for a call in Thumb or ARM state.
+//
+// The BIOS reads the call number back out of the instruction, which
+// holds it in the low byte in Thumb state but in bits 16-23 in ARM
+// state.  Every number in the table is written as `0xNN`, so appending
+// zeros does the shift.
+#[cfg(any(target_arch = "arm", test))]
+macro_rules! swi_instruction {
+    (thumb, $name:ident) => (concat!("swi ", swi_number!($name)));
+    (arm, $name:ident) => (concat!("swi ", swi_number!($name), "0000"));
+}
+
+swi_table! {
+    /// Restarts the game.
+    SoftReset = 0x00,
+    /// Clears memory and registers.
+    RegisterRamReset = 0x01,
+    /// Waits for any enabled interrupt.
+    Halt = 0x02,
+    /// Waits for a keypad, cartridge or serial interrupt in low power
+    /// mode.
+    Stop = 0x03,
+    /// Waits for specific interrupts.
+    IntrWait = 0x04,
+    /// Waits for the next vblank interrupt.
+    VBlankIntrWait = 0x05,
+    /// Signed division.
+    Div = 0x06,
+    /// Signed division with the arguments swapped.
+    DivArm = 0x07,
+    /// Square root.
+    Sqrt = 0x08,
+    /// Arctangent.
+    ArcTan = 0x09,
+    /// Arctangent of a vector.
+    ArcTan2 = 0x0A,
+    /// Copies or fills memory.
+    CpuSet = 0x0B,
+    /// Copies or fills memory 32 bytes at a time.
+    CpuFastSet = 0x0C,
+    /// Checksums the BIOS.
+    GetBiosChecksum = 0x0D,
+    /// Calculates background affine parameters.
+    BgAffineSet = 0x0E,
+    /// Calculates object affine matrices.
+    ObjAffineSet = 0x0F,
+    /// Widens packed units.
+    BitUnPack = 0x10,
+    /// LZ77 decompression, 8 bits at a time.
+    Lz77UnCompWram = 0x11,
+    /// LZ77 decompression, 16 bits at a time.
+    Lz77UnCompVram = 0x12,
+    /// Huffman decompression.
+    HuffUnComp = 0x13,
+    /// Run length decompression, 8 bits at a time.
+    RlUnCompWram = 0x14,
+    /// Run length decompression, 16 bits at a time.
+    RlUnCompVram = 0x15,
+    /// Undoes an 8-bit difference filter, 8 bits at a time.
+    Diff8bitUnFilterWram = 0x16,
+    /// Undoes an 8-bit difference filter, 16 bits at a time.
+    Diff8bitUnFilterVram = 0x17,
+    /// Undoes a 16-bit difference filter.
+    Diff16bitUnFilter = 0x18,
+    /// Moves the sound bias level.
+    SoundBias = 0x19,
+    /// Sample rate for a MIDI key.
+    MidiKey2Freq = 0x1F,
+    /// Sends a multiboot image.
+    MultiBoot = 0x25,
+    /// Reboots the system.
+    HardReset = 0x26,
+}
+
+// The `swi` instruction for a call, for use as an `asm!` template, in
+// ARM state with the `arm-state` feature.
+#[cfg(all(target_arch = "arm", not(feature = "arm-state")))]
+macro_rules! swi {
+    ($name:ident) => (swi_instruction!(thumb, $name));
+}
+
+#[cfg(all(target_arch = "arm", feature = "arm-state"))]
+macro_rules! swi {
+    ($name:ident) => (swi_instruction!(arm, $name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Swi, INSTRUCTIONS};
+
+    // The comment field of a `swi 0x...` instruction.
+    fn operand(instruction: &str) -> u32 {
+        let mut parts = instruction.splitn(2, "swi 0x");
+        assert_eq!(Some(""), parts.next(), "{}", instruction);
+        u32::from_str_radix(parts.next().unwrap(), 16).unwrap()
+    }
+
+    #[test]
+    fn instructions_hold_the_call_numbers() {
+        for &(swi, thumb, arm) in INSTRUCTIONS {
+            assert_eq!(swi.number() as u32, operand(thumb), "{:?}", swi);
+            assert_eq!((swi.number() as u32) << 16, operand(arm), "{:?}", swi);
+        }
+        assert_eq!(Swi::all().len(), INSTRUCTIONS.len());
+    }
+}
@@ -0,0 +1,144 @@
+use core::marker::PhantomData;
+
+/// `bios_checksum` on a game boy advance.
+pub const GBA_BIOS_CHECKSUM: u32 = 0xBAAE_187F;
+
+/// `bios_checksum` on a nintendo DS in game boy advance mode.
+pub const NDS_BIOS_CHECKSUM: u32 = 0xBAAE_1880;
+
+/// Calculates a checksum of the BIOS, which can be compared with
+/// `GBA_BIOS_CHECKSUM` and `NDS_BIOS_CHECKSUM` to detect the system.
+#[inline]
+pub fn bios_checksum() -> u32 {
+    let out: u32;
+    unsafe {
+        asm!(swi!(GetBiosChecksum)
+             : "={r0}"(out)
+             :
+             : "r1", "r2", "r3");
+    }
+    out
+}
+
+/// Serial modes for sending a multiboot image.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MultiBootMode {
+    /// 32-bit normal mode at 256KHz, to one client.
+    Normal256Khz = 0,
+
+    /// Multiplayer mode at 115KHz, to up to three clients.
+    Multiplayer = 1,
+
+    /// 32-bit normal mode at 2MHz, to one client.
+    Normal2Mhz = 2,
+}
+
+/// Errors from `multiboot`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MultiBootError {
+    /// The image isn't between 0x1C0 and 0x40000 bytes, or the part
+    /// after the header isn't a multiple of 16 bytes.
+    ImageSize(usize),
+
+    /// The transfer failed.
+    Failed,
+}
+
+/// Size of the cartridge header, which isn't sent by `multiboot`.
+pub const MULTIBOOT_HEADER_SIZE: usize = 0xC0;
+
+/// Largest multiboot image, which has to fit in EWRAM.
+pub const MULTIBOOT_MAX_SIZE: usize = 0x4_0000;
+
+/// Parameters for `multiboot`, filled in from the handshake with the
+/// clients.
+#[repr(C)]
+pub struct MultiBootParam<'a> {
+    _reserved1: [u32; 5],
+
+    /// Handshake byte, 0x11 plus the sum of the client data
+    pub handshake_data: u8,
+    _padding: u8,
+    _handshake_timeout: u16,
+    _probe_count: u8,
+
+    /// Random data sent by each client
+    pub client_data: [u8; 3],
+
+    /// Palette and speed of the boot logo animation
+    pub palette_data: u8,
+    _response_bit: u8,
+
+    /// Bits 1-3 set for each client that responded
+    pub client_bit: u8,
+    _reserved2: u8,
+
+    boot_start: *const u8,
+    boot_end: *const u8,
+    _master: *const u8,
+    _reserved3: [*const u8; 3],
+    _system_work2: [u32; 4],
+    _send_flag: u8,
+    _probe_target_bit: u8,
+    _check_wait: u8,
+    _server_type: u8,
+
+    _image: PhantomData<&'a [u8]>,
+}
+
+impl<'a> MultiBootParam<'a> {
+    /// Prepares to send `image`, a whole multiboot game including its
+    /// cartridge header.
+    pub fn new(image: &'a [u8]) -> Result<MultiBootParam<'a>, MultiBootError> {
+        let len = image.len();
+        if len < MULTIBOOT_HEADER_SIZE + 0x100 || len > MULTIBOOT_MAX_SIZE ||
+            (len - MULTIBOOT_HEADER_SIZE) & 0xF != 0 {
+            return Err(MultiBootError::ImageSize(len));
+        }
+        Ok(MultiBootParam {
+            _reserved1: [0; 5],
+            handshake_data: 0,
+            _padding: 0,
+            _handshake_timeout: 0,
+            _probe_count: 0,
+            client_data: [0xFF; 3],
+            palette_data: 0,
+            _response_bit: 0,
+            client_bit: 0,
+            _reserved2: 0,
+            boot_start: image[MULTIBOOT_HEADER_SIZE..].as_ptr(),
+            boot_end: image[len..].as_ptr(),
+            _master: 0 as *const u8,
+            _reserved3: [0 as *const u8; 3],
+            _system_work2: [0; 4],
+            _send_flag: 0,
+            _probe_target_bit: 0,
+            _check_wait: 0,
+            _server_type: 0,
+            _image: PhantomData,
+        })
+    }
+}
+
+/// Sends a multiboot image to the clients named in `param`.
+///
+/// The handshake and header transfer must already be done, leaving
+/// `client_bit`, `client_data`, `palette_data` and `handshake_data`
+/// filled in.  This takes a few seconds, with interrupts disabled.
+#[inline]
+pub fn multiboot(param: &mut MultiBootParam, mode: MultiBootMode) -> Result<(), MultiBootError> {
+    let out: u32;
+    unsafe {
+        asm!(swi!(MultiBoot)
+             : "={r0}"(out)
+             : "{r0}"(param as *mut MultiBootParam)
+             , "{r1}"(mode as u32)
+             : "r1", "r2", "r3", "memory"
+             : "volatile");
+    }
+    if out == 0 {
+        Ok(())
+    } else {
+        Err(MultiBootError::Failed)
+    }
+}
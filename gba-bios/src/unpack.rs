@@ -0,0 +1,85 @@
+/// How `bit_unpack` widens each unit of its source.
+///
+/// Units are read and written least significant bits first, so each
+/// source byte of 1-bit units becomes eight destination units.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct UnpackInfo {
+    source_len: u16,
+    source_width: u8,
+    dest_width: u8,
+    offset: u32,
+}
+
+const OFFSET_ZERO: u32 = 1 << 31;
+
+impl UnpackInfo {
+    /// Unpacks `source_width` bit units into `dest_width` bit units.
+    ///
+    /// # Panics
+    ///
+    /// If `source_width` isn't 1, 2, 4 or 8, or `dest_width` isn't
+    /// 1, 2, 4, 8, 16 or 32, or `dest_width` is less than
+    /// `source_width`.
+    pub fn new(source_width: u8, dest_width: u8) -> UnpackInfo {
+        assert!([1, 2, 4, 8].contains(&source_width));
+        assert!([1, 2, 4, 8, 16, 32].contains(&dest_width));
+        assert!(dest_width >= source_width);
+        UnpackInfo {
+            source_len: 0,
+            source_width: source_width,
+            dest_width: dest_width,
+            offset: 0,
+        }
+    }
+
+    /// Adds `offset` to each unpacked unit.
+    ///
+    /// Zero units are left as zero unless `offset_zero` is set, which
+    /// keeps e.g. transparent pixels transparent when shifting colors.
+    pub fn set_offset(mut self, offset: u32, offset_zero: bool) -> UnpackInfo {
+        self.offset = offset & !OFFSET_ZERO;
+        if offset_zero {
+            self.offset |= OFFSET_ZERO;
+        }
+        self
+    }
+
+    /// Bytes of output from unpacking `source_len` bytes.
+    pub fn unpacked_len(&self, source_len: usize) -> usize {
+        source_len * self.dest_width as usize / self.source_width as usize
+    }
+}
+
+/// Unpacks narrow units into wider ones, e.g. to expand a 1bpp font
+/// into 4bpp tiles.
+///
+/// # Panics
+///
+/// If `source` is longer than 0xFFFF bytes, or the unpacked data
+/// won't fit in `dest`.
+///
+/// # Examples
+///
+/// ```
+/// use gba_bios::{bit_unpack, UnpackInfo};
+///
+/// let mut tile = [0u32; 2];
+/// bit_unpack(&[0x81, 0xFF], &mut tile, UnpackInfo::new(1, 4));
+/// assert_eq!([0x10000001, 0x11111111], tile);
+/// ```
+#[inline]
+pub fn bit_unpack(source: &[u8], dest: &mut [u32], mut info: UnpackInfo) {
+    assert!(source.len() <= 0xFFFF);
+    assert!(info.unpacked_len(source.len()) <= dest.len() * 4);
+    info.source_len = source.len() as u16;
+    unsafe {
+        asm!(swi!(BitUnPack)
+             :
+             : "{r0}"(source.as_ptr())
+             , "{r1}"(dest.as_mut_ptr())
+             , "{r2}"(&info as *const UnpackInfo)
+             : "r0", "r1", "r2", "r3", "memory"
+             : "volatile");
+    }
+}
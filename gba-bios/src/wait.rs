@@ -17,7 +17,7 @@ pub use gba_hw::interrupts::SourceSet;
 /// function.
 #[inline(always)]
 pub fn wait_for_any_enabled_interrupt() {
-    unsafe {asm!("swi 0x06");}
+    unsafe {asm!(swi!(Halt));}
 }
 
 /// Puts CPU into super-low power state until an interrupt occurs.
@@ -38,7 +38,7 @@ pub fn wait_for_any_enabled_interrupt() {
 /// actually fire.
 #[inline(always)]
 pub fn stop_and_wait_for_any_enabled_interrupt() {
-    unsafe {asm!("swi 0x03");}
+    unsafe {asm!(swi!(Stop));}
 }
 
 
@@ -63,7 +63,7 @@ pub fn stop_and_wait_for_any_enabled_interrupt() {
 pub unsafe fn wait_for_interrupt(interrupts: SourceSet) {
     let interrupts_bits = interrupts.bits();
     let mode = 0u16;
-    asm!("swi 0x04"
+    asm!(swi!(IntrWait)
          :
          : "{r0}"(mode)
          , "{r1}"(interrupts_bits));
@@ -91,7 +91,7 @@ pub unsafe fn wait_for_interrupt(interrupts: SourceSet) {
 pub unsafe fn wait_for_new_interrupt(interrupts: SourceSet) {
     let interrupts_bits = interrupts.bits();
     let mode = 1u16;
-    asm!("swi 0x04"
+    asm!(swi!(IntrWait)
          :
          : "{r0}"(mode)
          , "{r1}"(interrupts_bits));
@@ -108,6 +108,120 @@ pub unsafe fn wait_for_new_interrupt(interrupts: SourceSet) {
 /// `IME` register.
 #[inline(always)]
 pub unsafe fn wait_for_new_vblank() {
-    asm!("swi 0x05":::"r0 r1");
+    asm!(swi!(VBlankIntrWait):::"r0", "r1");
 }
 
+
+/// Reasons the safe `try_wait..` functions refuse to wait.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WaitError {
+    /// The set of interrupts to wait for was empty.
+    NoSources,
+
+    /// These sources are not enabled in `interrupts::enable`.
+    NotEnabled(SourceSet),
+
+    /// These sources are not enabled in their peripheral, e.g.
+    /// `VBLANK` without the vblank IRQ bit set in `display_status`.
+    PeripheralDisabled(SourceSet),
+
+    /// `IME` is off, e.g. inside a critical section, and waiting would
+    /// turn it on.
+    MasterDisabled,
+}
+
+// Sources in `interrupts` whose peripheral is not set up to raise them.
+//
+// Cartridge interrupts can't be checked, so they are assumed enabled.
+fn peripheral_disabled(interrupts: SourceSet) -> SourceSet {
+    use gba_hw::{dma, keypad, sio, timers, video};
+    use gba_hw::interrupts::*;
+
+    let mut disabled = SourceSet::empty();
+    unsafe {
+        let display_status = video::display_status().read();
+        let timers = [timers::timer0().control.read(),
+                      timers::timer1().control.read(),
+                      timers::timer2().control.read(),
+                      timers::timer3().control.read()];
+        let dmas = [dma::channel0().control.read(),
+                    dma::channel1().control.read(),
+                    dma::channel2().control.read(),
+                    dma::channel3().control.read()];
+        let checks = [
+            (VBLANK, display_status.vblank_irq()),
+            (HBLANK, display_status.hblank_irq()),
+            (VCOUNTER, display_status.vcounter_irq()),
+            (TIMER_0, timers[0].overflow_irq() && timers[0].running()),
+            (TIMER_1, timers[1].overflow_irq() && timers[1].running()),
+            (TIMER_2, timers[2].overflow_irq() && timers[2].running()),
+            (TIMER_3, timers[3].overflow_irq() && timers[3].running()),
+            (SIO, sio::control().read().irq()),
+            (DMA_0, dmas[0].complete_irq() && dmas[0].enabled()),
+            (DMA_1, dmas[1].complete_irq() && dmas[1].enabled()),
+            (DMA_2, dmas[2].complete_irq() && dmas[2].enabled()),
+            (DMA_3, dmas[3].complete_irq() && dmas[3].enabled()),
+            (KEYPAD, keypad::key_control().read().irq()),
+        ];
+        for &(source, enabled) in checks.iter() {
+            if interrupts.contains(source) && !enabled {
+                disabled.insert(source);
+            }
+        }
+    }
+    disabled
+}
+
+fn check_enabled(interrupts: SourceSet) -> Result<(), WaitError> {
+    use gba_hw::interrupts::MasterEnable;
+
+    if interrupts.is_empty() {
+        return Err(WaitError::NoSources);
+    }
+    if unsafe { gba_hw::interrupts::master_enable().read() } != MasterEnable::InterruptsEnabled {
+        return Err(WaitError::MasterDisabled);
+    }
+    let not_enabled = interrupts - unsafe { gba_hw::interrupts::enable().read() };
+    if !not_enabled.is_empty() {
+        return Err(WaitError::NotEnabled(not_enabled));
+    }
+    let disabled = peripheral_disabled(interrupts);
+    if !disabled.is_empty() {
+        return Err(WaitError::PeripheralDisabled(disabled));
+    }
+    Ok(())
+}
+
+/// Safe version of `wait_for_interrupt`.
+///
+/// Checks that every source in `interrupts` is enabled, both in `IE`
+/// and in its peripheral, returning an error rather than waiting
+/// forever if not.  `IME` must already be on, so that waiting can't
+/// turn it on behind a critical section's back.
+///
+/// Note that this must not be called from an interrupt handler, and
+/// that the interrupt handler must acknowledge the sources in
+/// `irq_acknowledge_bios`, or this will still never return.
+pub fn try_wait_for_interrupt(interrupts: SourceSet) -> Result<(), WaitError> {
+    check_enabled(interrupts)?;
+    unsafe { wait_for_interrupt(interrupts) };
+    Ok(())
+}
+
+/// Safe version of `wait_for_new_interrupt`.
+///
+/// See `try_wait_for_interrupt` for the checks performed.
+pub fn try_wait_for_new_interrupt(interrupts: SourceSet) -> Result<(), WaitError> {
+    check_enabled(interrupts)?;
+    unsafe { wait_for_new_interrupt(interrupts) };
+    Ok(())
+}
+
+/// Safe version of `wait_for_new_vblank`.
+///
+/// See `try_wait_for_interrupt` for the checks performed.
+pub fn try_wait_for_new_vblank() -> Result<(), WaitError> {
+    check_enabled(gba_hw::interrupts::VBLANK)?;
+    unsafe { wait_for_new_vblank() };
+    Ok(())
+}
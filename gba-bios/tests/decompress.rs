@@ -0,0 +1,116 @@
+//! Bad compressed data, both rejected up front and given to the host
+//! fallbacks unchecked, which must stop at the end of the data and the
+//! destination.
+
+extern crate gba_bios;
+extern crate gba_compress;
+
+use gba_bios::{Compressed, DecompressError};
+use gba_compress::huffman::{self, SymbolSize};
+use gba_compress::{diff, lz77, run_length, to_words};
+
+fn data() -> Vec<u8> {
+    (0..2000u32).map(|i| (i * 7 % 13 + i / 50 % 3) as u8).collect()
+}
+
+fn unchecked(words: &[u32]) -> Compressed {
+    unsafe { Compressed::new_unchecked(words).unwrap() }
+}
+
+#[test]
+fn truncated_data_is_rejected() {
+    let data = data();
+    let streams = [lz77::compress(&data).unwrap(),
+                   run_length::compress(&data).unwrap(),
+                   huffman::compress(&data, SymbolSize::Four).unwrap(),
+                   huffman::compress(&data, SymbolSize::Eight).unwrap(),
+                   diff::filter8(&data).unwrap(),
+                   diff::filter16(&data).unwrap()];
+    for stream in streams.iter() {
+        let words = to_words(stream);
+        assert!(Compressed::new(&words).is_ok());
+        for len in 1..words.len() - 1 {
+            assert_eq!(Err(DecompressError::Truncated),
+                       Compressed::new(&words[..len]).map(|_| ()));
+        }
+    }
+}
+
+#[test]
+fn truncated_data_stops_the_host_fallbacks() {
+    let data = data();
+    let mut dest = vec![0u8; data.len()];
+
+    let words = to_words(&lz77::compress(&data).unwrap());
+    let source = unchecked(&words[..words.len() / 2]);
+    assert_eq!(Err(DecompressError::Truncated),
+               gba_bios::lz77_uncompress_wram(&source, &mut dest));
+
+    let words = to_words(&run_length::compress(&data).unwrap());
+    let source = unchecked(&words[..words.len() / 2]);
+    assert_eq!(Err(DecompressError::Truncated),
+               gba_bios::run_length_uncompress_wram(&source, &mut dest));
+
+    let words = to_words(&diff::filter8(&data).unwrap());
+    let source = unchecked(&words[..words.len() / 2]);
+    assert_eq!(Err(DecompressError::Truncated),
+               gba_bios::diff8_unfilter_wram(&source, &mut dest));
+
+    let mut dest = vec![0u32; data.len() / 4];
+    let words = to_words(&huffman::compress(&data, SymbolSize::Eight).unwrap());
+    let source = unchecked(&words[..words.len() / 2]);
+    assert_eq!(Err(DecompressError::Truncated),
+               gba_bios::huffman_uncompress(&source, &mut dest));
+}
+
+#[test]
+fn references_before_the_start_are_rejected() {
+    // 8 bytes, starting with a back-reference 1 byte back
+    let words = [0x0000_0810, 0x0000_0080];
+    assert_eq!(Err(DecompressError::InvalidReference),
+               Compressed::new(&words).map(|_| ()));
+
+    let mut dest = [0u8; 8];
+    assert_eq!(Err(DecompressError::InvalidReference),
+               gba_bios::lz77_uncompress_wram(&unchecked(&words), &mut dest));
+}
+
+#[test]
+fn nodes_outside_the_tree_are_rejected() {
+    // a one node tree whose root points far past itself
+    let words = [0x0000_0428, 0x0000_3F01, 0xFFFF_FFFF];
+    assert_eq!(Err(DecompressError::InvalidTree), Compressed::new(&words).map(|_| ()));
+
+    let mut dest = [0u32; 1];
+    assert_eq!(Err(DecompressError::InvalidTree),
+               gba_bios::huffman_uncompress(&unchecked(&words), &mut dest));
+}
+
+#[test]
+fn overshooting_blocks_need_room() {
+    // 2 bytes: a literal, then a back-reference copying 18
+    let words = [0x0000_0210, 0x00F0_4140, 0];
+    let source = Compressed::new(&words).unwrap();
+    assert_eq!(19, source.written_size());
+    let mut dest = [0u8; 2];
+    assert_eq!(Err(DecompressError::DestinationTooSmall { needed: 19, available: 2 }),
+               gba_bios::lz77_uncompress_wram(&source, &mut dest));
+    let mut dest = [0u8; 19];
+    assert_eq!(Ok(2), gba_bios::lz77_uncompress_wram(&source, &mut dest));
+    assert_eq!([0x41; 2], dest[..2]);
+
+    // a run of 130 bytes, for a size of 1
+    let words = [0x0000_0130, 0x0000_41FF];
+    let source = Compressed::new(&words).unwrap();
+    assert_eq!(130, source.written_size());
+    let mut dest = [0u8; 1];
+    assert_eq!(Err(DecompressError::DestinationTooSmall { needed: 130, available: 1 }),
+               gba_bios::run_length_uncompress_wram(&source, &mut dest));
+
+    // unchecked, the host fallbacks stop at the end of the destination
+    let mut dest = [0u8; 1];
+    assert_eq!(Ok(1), gba_bios::run_length_uncompress_wram(&unchecked(&words), &mut dest));
+    assert_eq!([0x41], dest);
+    let mut dest = [0u16; 1];
+    assert_eq!(Ok(1), gba_bios::run_length_uncompress_vram(&unchecked(&words), &mut dest));
+}
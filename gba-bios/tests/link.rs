@@ -0,0 +1,188 @@
+//! The multiboot handshake against simulated clients, some of which
+//! misbehave.
+
+extern crate gba_bios;
+
+use gba_bios::{handshake, normal_replies, Progress, SendError, Transport};
+
+const SEED: u16 = 0x42;
+
+fn image() -> Vec<u8> {
+    (0..0x1C0).map(|i| i as u8).collect()
+}
+
+/// A client that answers as the BIOS does, unless told otherwise.
+struct Client {
+    /// Bit 1-3 for the client number.
+    bit: u16,
+    header: Vec<u16>,
+    // header halfwords received, once the master has found us
+    received: Option<usize>,
+    probes: usize,
+
+    /// Answers the header halfword at this index with `0x1234`.
+    wrong_ack_at: Option<usize>,
+    /// Never picks its random data.
+    never_seeds: bool,
+}
+
+impl Client {
+    fn new(client: u8) -> Client {
+        let image = image();
+        Client {
+            bit: 1 << client,
+            header: image[..0xC0].chunks(2).map(|b| b[0] as u16 | (b[1] as u16) << 8).collect(),
+            received: None,
+            probes: 0,
+            wrong_ack_at: None,
+            never_seeds: false,
+        }
+    }
+
+    fn reply(&mut self, data: u16) -> u16 {
+        match (self.received, data >> 8) {
+            // needs a few probes before it's ready
+            (None, 0x62) if self.probes < 3 => {
+                self.probes += 1;
+                0xFFFF
+            }
+            (None, 0x61) => {
+                self.received = Some(0);
+                0x7200 | self.bit
+            }
+            (None, _) => 0x7200 | self.bit,
+            (Some(n), _) if n < 0x60 => {
+                assert_eq!(self.header[n], data);
+                self.received = Some(n + 1);
+                if self.wrong_ack_at == Some(n) {
+                    0x1234
+                } else {
+                    ((0x60 - n as u16) << 8) | self.bit
+                }
+            }
+            (Some(0x60), 0x62) => {
+                self.received = Some(0x61);
+                self.bit
+            }
+            (_, 0x62) => 0x7200 | self.bit,
+            (_, 0x63) if self.never_seeds => 0x7200 | self.bit,
+            (_, 0x63) => 0x7300 | SEED,
+            _ => 0x7300,
+        }
+    }
+}
+
+/// Multi-player mode, where the clients answer in their own slots.
+struct MultiPlayer {
+    clients: Vec<Client>,
+    exchanges: usize,
+    /// Transfers fail from this exchange on.
+    fail_from: Option<usize>,
+    delays: usize,
+}
+
+impl MultiPlayer {
+    fn new(clients: Vec<Client>) -> MultiPlayer {
+        MultiPlayer { clients, exchanges: 0, fail_from: None, delays: 0 }
+    }
+}
+
+impl Transport for MultiPlayer {
+    fn exchange(&mut self, data: u16) -> Option<[u16; 3]> {
+        self.exchanges += 1;
+        match self.fail_from {
+            Some(fail_from) if self.exchanges >= fail_from => return None,
+            _ => {}
+        }
+        let mut replies = [0xFFFF; 3];
+        for client in &mut self.clients {
+            let slot = client.bit.trailing_zeros() as usize - 1;
+            replies[slot] = client.reply(data);
+        }
+        Some(replies)
+    }
+
+    fn delay(&mut self) {
+        self.delays += 1;
+    }
+}
+
+/// 32-bit normal mode, where the one client answers in the high
+/// halfword of each transfer, after the master's own bits have been
+/// shifted out of the low one.
+struct Normal(Client);
+
+impl Transport for Normal {
+    fn exchange(&mut self, data: u16) -> Option<[u16; 3]> {
+        let reply = self.0.reply(data);
+        Some(normal_replies((reply as u32) << 16 | 0xFFFF))
+    }
+
+    fn delay(&mut self) {}
+}
+
+#[test]
+fn missing_clients_give_up() {
+    let mut transport = MultiPlayer::new(Vec::new());
+    assert_eq!(Err(SendError::NoClients), handshake(&mut transport, &image(), 0xC1, |_| {}));
+    // about four seconds of waiting
+    assert_eq!(63, transport.delays);
+}
+
+#[test]
+fn wrong_header_acks_are_rejected() {
+    let mut client = Client::new(1);
+    client.wrong_ack_at = Some(5);
+    let mut transport = MultiPlayer::new(vec![client]);
+    let mut sent = 0;
+    let result = handshake(&mut transport, &image(), 0xC1, |progress| {
+        if let Progress::Header(n) = progress {
+            sent = n;
+        }
+    });
+    assert_eq!(Err(SendError::Client { client: 1, reply: 0x1234 }), result);
+    assert_eq!(5, sent);
+}
+
+#[test]
+fn clients_that_never_seed_time_out() {
+    let mut client = Client::new(2);
+    client.never_seeds = true;
+    let mut transport = MultiPlayer::new(vec![client]);
+    assert_eq!(Err(SendError::Seed), handshake(&mut transport, &image(), 0xC1, |_| {}));
+}
+
+#[test]
+fn transfer_failures_stop_the_handshake() {
+    let mut transport = MultiPlayer::new(vec![Client::new(1)]);
+    // well into the header
+    transport.fail_from = Some(50);
+    let mut progress = Vec::new();
+    let result = handshake(&mut transport, &image(), 0xC1, |p| progress.push(p));
+    assert_eq!(Err(SendError::Transfer), result);
+    assert_eq!(Some(&Progress::Found(0x02)), progress.first());
+    assert!(progress.contains(&Progress::Header(1)));
+    assert!(!progress.contains(&Progress::Header(0x60)));
+    assert!(!progress.contains(&Progress::Seeded));
+}
+
+#[test]
+fn every_client_is_handshaken() {
+    let mut transport = MultiPlayer::new(vec![Client::new(1), Client::new(3)]);
+    let result = handshake(&mut transport, &image(), 0xC1, |_| {}).unwrap();
+    assert_eq!(0x0A, result.client_bit);
+    assert_eq!([SEED as u8, 0xFF, SEED as u8], result.client_data);
+    assert_eq!(0xC1, result.palette_data);
+    // 0x11 + 0x42 + 0xFF + 0x42
+    assert_eq!(0x94, result.handshake_data);
+}
+
+#[test]
+fn normal_mode_clients_answer_as_client_1() {
+    let mut transport = Normal(Client::new(1));
+    let result = handshake(&mut transport, &image(), 0xC1, |_| {}).unwrap();
+    assert_eq!(0x02, result.client_bit);
+    assert_eq!([SEED as u8, 0xFF, 0xFF], result.client_data);
+    // 0x11 + 0x42 + 0xFF + 0xFF
+    assert_eq!(0x51, result.handshake_data);
+}
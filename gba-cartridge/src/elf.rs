@@ -0,0 +1,64 @@
+//! Just enough of ELF to find a linked game's loadable segments.
+
+use Error;
+
+const PT_LOAD: u32 = 1;
+
+/// A segment to be loaded into memory.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Segment<'a> {
+    /// The load address, where the segment lives in the cartridge.
+    pub lma: u32,
+
+    /// The address the segment runs at, which differs from `lma` for
+    /// sections copied into RAM at startup.
+    pub vma: u32,
+
+    /// The segment's contents.
+    pub data: &'a [u8],
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, Error> {
+    data.get(offset..offset + 2)
+        .map(|b| b[0] as u16 | (b[1] as u16) << 8)
+        .ok_or(Error::Elf("truncated"))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, Error> {
+    data.get(offset..offset + 4)
+        .map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+        .ok_or(Error::Elf("truncated"))
+}
+
+/// Parses a 32-bit little-endian ARM ELF file, returning its loadable
+/// segments that have contents.
+pub fn segments<'a>(elf: &'a [u8]) -> Result<Vec<Segment<'a>>, Error> {
+    if elf.get(0..4) != Some(b"\x7FELF") {
+        return Err(Error::Elf("not an ELF file"));
+    }
+    if elf.get(4..6) != Some(&[1, 1][..]) {
+        return Err(Error::Elf("not a 32-bit little-endian ELF file"));
+    }
+    if u16_at(elf, 18)? != 40 {
+        return Err(Error::Elf("not an ARM ELF file"));
+    }
+
+    let ph_offset = u32_at(elf, 28)? as usize;
+    let ph_size = u16_at(elf, 42)? as usize;
+    let ph_count = u16_at(elf, 44)? as usize;
+    let mut segments = Vec::new();
+    for i in 0..ph_count {
+        let header = ph_offset + i * ph_size;
+        let offset = u32_at(elf, header + 4)? as usize;
+        let file_size = u32_at(elf, header + 16)? as usize;
+        if u32_at(elf, header)? != PT_LOAD || file_size == 0 {
+            continue;
+        }
+        segments.push(Segment {
+            vma: u32_at(elf, header + 8)?,
+            lma: u32_at(elf, header + 12)?,
+            data: elf.get(offset..offset + file_size).ok_or(Error::Elf("truncated segment"))?,
+        });
+    }
+    Ok(segments)
+}
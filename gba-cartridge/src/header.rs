@@ -0,0 +1,177 @@
+//! The 192-byte header at the start of every cartridge.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_cartridge::header::{self, Header};
+//!
+//! let mut rom = vec![0u8; 0x200];
+//! let header = Header {
+//!     title: "DEMO".to_string(),
+//!     game_code: "ADMO".to_string(),
+//!     maker_code: "01".to_string(),
+//!     version: 0,
+//! };
+//! header.write(&mut rom, 0xC0).unwrap();
+//! assert_eq!(Ok(()), header::verify(&rom));
+//!
+//! rom[0xA0] = b'X';
+//! assert_eq!(Err(header::VerifyError::Checksum), header::verify(&rom));
+//! ```
+
+use Error;
+
+/// Size of the header in bytes.
+pub const SIZE: usize = 0xC0;
+
+/// The Nintendo logo, which the BIOS checks before booting.
+pub const LOGO: [u8; 156] = [
+    0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A,
+    0x84, 0xE4, 0x09, 0xAD, 0x11, 0x24, 0x8B, 0x98, 0xC0, 0x81, 0x7F, 0x21,
+    0xA3, 0x52, 0xBE, 0x19, 0x93, 0x09, 0xCE, 0x20, 0x10, 0x46, 0x4A, 0x4A,
+    0xF8, 0x27, 0x31, 0xEC, 0x58, 0xC7, 0xE8, 0x33, 0x82, 0xE3, 0xCE, 0xBF,
+    0x85, 0xF4, 0xDF, 0x94, 0xCE, 0x4B, 0x09, 0xC1, 0x94, 0x56, 0x8A, 0xC0,
+    0x13, 0x72, 0xA7, 0xFC, 0x9F, 0x84, 0x4D, 0x73, 0xA3, 0xCA, 0x9A, 0x61,
+    0x58, 0x97, 0xA3, 0x27, 0xFC, 0x03, 0x98, 0x76, 0x23, 0x1D, 0xC7, 0x61,
+    0x03, 0x04, 0xAE, 0x56, 0xBF, 0x38, 0x84, 0x00, 0x40, 0xA7, 0x0E, 0xFD,
+    0xFF, 0x52, 0xFE, 0x03, 0x6F, 0x95, 0x30, 0xF1, 0x97, 0xFB, 0xC0, 0x85,
+    0x60, 0xD6, 0x80, 0x25, 0xA9, 0x63, 0xBE, 0x03, 0x01, 0x4E, 0x38, 0xE2,
+    0xF9, 0xA2, 0x34, 0xFF, 0xBB, 0x3E, 0x03, 0x44, 0x78, 0x00, 0x90, 0xCB,
+    0x88, 0x11, 0x3A, 0x94, 0x65, 0xC0, 0x7C, 0x63, 0x87, 0xF0, 0x3C, 0xAF,
+    0xD6, 0x25, 0xE4, 0x8B, 0x38, 0x0A, 0xAC, 0x72, 0x21, 0xD4, 0xF8, 0x07,
+];
+
+const LOGO_OFFSET: usize = 0x04;
+const TITLE_OFFSET: usize = 0xA0;
+const GAME_CODE_OFFSET: usize = 0xAC;
+const MAKER_CODE_OFFSET: usize = 0xB0;
+const FIXED_OFFSET: usize = 0xB2;
+const FIXED_VALUE: u8 = 0x96;
+const VERSION_OFFSET: usize = 0xBC;
+const CHECKSUM_OFFSET: usize = 0xBD;
+
+/// The game's identity, as written into the header.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Header {
+    /// Up to 12 upper case ASCII characters.
+    pub title: String,
+
+    /// 4 upper case ASCII characters, e.g. `AXVE`.
+    pub game_code: String,
+
+    /// 2 ASCII characters identifying the publisher, e.g. `01`.
+    pub maker_code: String,
+
+    /// The software version.
+    pub version: u8,
+}
+
+impl Default for Header {
+    fn default() -> Header {
+        Header {
+            title: String::new(),
+            game_code: "0000".to_string(),
+            maker_code: "00".to_string(),
+            version: 0,
+        }
+    }
+}
+
+fn check_field(name: &'static str, value: &str, min: usize, max: usize) -> Result<(), Error> {
+    if value.len() < min || value.len() > max ||
+        !value.bytes().all(|b| b.is_ascii_graphic() || b == b' ') {
+        return Err(Error::Field(name, value.to_string()));
+    }
+    Ok(())
+}
+
+/// The ARM branch instruction at `from` that jumps to `to`.
+pub fn branch(from: u32, to: u32) -> u32 {
+    0xEA00_0000 | (to.wrapping_sub(from).wrapping_sub(8) >> 2) & 0xFF_FFFF
+}
+
+/// The header complement check over bytes 0xA0-0xBC.
+pub fn checksum(header: &[u8]) -> u8 {
+    header[TITLE_OFFSET..CHECKSUM_OFFSET]
+        .iter()
+        .fold(0u8, |sum, &b| sum.wrapping_sub(b))
+        .wrapping_sub(0x19)
+}
+
+impl Header {
+    /// Checks the fields, then writes the whole header into the start
+    /// of `rom`, with an entry point branching to offset `entry`.
+    pub fn write(&self, rom: &mut [u8], entry: u32) -> Result<(), Error> {
+        check_field("title", &self.title, 0, 12)?;
+        check_field("game code", &self.game_code, 4, 4)?;
+        check_field("maker code", &self.maker_code, 2, 2)?;
+        if rom.len() < SIZE {
+            return Err(Error::TooSmall(rom.len()));
+        }
+
+        let header = &mut rom[..SIZE];
+        for b in header.iter_mut() {
+            *b = 0;
+        }
+        let branch = branch(0, entry);
+        header[..4].copy_from_slice(&[branch as u8, (branch >> 8) as u8,
+                                      (branch >> 16) as u8, (branch >> 24) as u8]);
+        header[LOGO_OFFSET..LOGO_OFFSET + LOGO.len()].copy_from_slice(&LOGO);
+        header[TITLE_OFFSET..TITLE_OFFSET + self.title.len()]
+            .copy_from_slice(self.title.as_bytes());
+        header[GAME_CODE_OFFSET..GAME_CODE_OFFSET + 4].copy_from_slice(self.game_code.as_bytes());
+        header[MAKER_CODE_OFFSET..MAKER_CODE_OFFSET + 2]
+            .copy_from_slice(self.maker_code.as_bytes());
+        header[FIXED_OFFSET] = FIXED_VALUE;
+        header[VERSION_OFFSET] = self.version;
+        header[CHECKSUM_OFFSET] = checksum(header);
+        Ok(())
+    }
+
+    /// The header on its own, with an entry point branching to offset
+    /// `entry`.
+    pub fn bytes(&self, entry: u32) -> Result<[u8; SIZE], Error> {
+        let mut header = [0u8; SIZE];
+        self.write(&mut header, entry)?;
+        Ok(header)
+    }
+}
+
+/// Reasons a cartridge image won't boot.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum VerifyError {
+    /// The image is smaller than the header.
+    TooSmall,
+
+    /// The first instruction isn't an ARM branch.
+    Entry,
+
+    /// The Nintendo logo is wrong.
+    Logo,
+
+    /// The fixed value at 0xB2 isn't 0x96.
+    FixedValue,
+
+    /// The complement check at 0xBD is wrong.
+    Checksum,
+}
+
+/// Checks that `rom` has a header the BIOS will accept.
+pub fn verify(rom: &[u8]) -> Result<(), VerifyError> {
+    if rom.len() < SIZE {
+        return Err(VerifyError::TooSmall);
+    }
+    if rom[3] != 0xEA {
+        return Err(VerifyError::Entry);
+    }
+    if rom[LOGO_OFFSET..LOGO_OFFSET + LOGO.len()] != LOGO[..] {
+        return Err(VerifyError::Logo);
+    }
+    if rom[FIXED_OFFSET] != FIXED_VALUE {
+        return Err(VerifyError::FixedValue);
+    }
+    if rom[CHECKSUM_OFFSET] != checksum(rom) {
+        return Err(VerifyError::Checksum);
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Builds game boy advance cartridge images from linked ELF files.
+//!
+//! The loadable segments are copied to their load addresses in the
+//! cartridge, and the header is checked or filled in, so the image
+//! boots without any outside tools.  Games can instead declare their
+//! header in `Cargo.toml`, see [`metadata`](metadata/index.html).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::{Read, Write};
+//! use gba_cartridge::{build, Options};
+//! use gba_cartridge::header::Header;
+//!
+//! let mut elf = Vec::new();
+//! File::open("target/gameboy-advance/release/game").unwrap().read_to_end(&mut elf).unwrap();
+//!
+//! let mut options = Options::default();
+//! let mut header = Header::default();
+//! header.title = "GAME".to_string();
+//! options.header = Some(header);
+//! options.pad = true;
+//! let rom = build(&elf, &options).unwrap();
+//! File::create("game.gba").unwrap().write_all(&rom).unwrap();
+//! ```
+
+use std::fmt;
+
+pub mod elf;
+pub mod header;
+pub mod metadata;
+
+use header::{Header, VerifyError};
+
+/// Address of the start of the cartridge.
+pub const CART_START: u32 = 0x0800_0000;
+
+/// Largest cartridge image.
+pub const MAX_SIZE: usize = 32 * 1024 * 1024;
+
+/// Address multiboot images are loaded at, the start of EWRAM.
+pub const MULTIBOOT_START: u32 = 0x0200_0000;
+
+/// Largest multiboot image.
+pub const MULTIBOOT_MAX_SIZE: usize = 0x4_0000;
+
+// The BIOS sends multiboot images after the header in 16 byte blocks,
+// and at least 0x100 bytes of them.
+const MULTIBOOT_BLOCK: usize = 0x10;
+const MULTIBOOT_MIN_SIZE: usize = header::SIZE + 0x100;
+
+/// Errors from building a cartridge image.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Error {
+    /// The ELF file couldn't be read.
+    Elf(&'static str),
+
+    /// The `[package.metadata.gba]` table couldn't be read.
+    Metadata(String),
+
+    /// A header field is the wrong length or has characters that
+    /// aren't printable ASCII.
+    Field(&'static str, String),
+
+    /// The image is too small to hold a header.
+    TooSmall(usize),
+
+    /// A segment would be loaded outside the cartridge, or outside
+    /// EWRAM for a multiboot image.
+    OutsideCartridge {
+        /// Load address of the segment.
+        lma: u32,
+        /// Size of the segment in bytes.
+        size: usize,
+    },
+
+    /// Two segments would be loaded at the same place.
+    Overlap(u32),
+
+    /// The finished image doesn't have a valid header.
+    Verify(VerifyError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Elf(reason) => write!(f, "couldn't read ELF: {}", reason),
+            Error::Metadata(ref reason) => write!(f, "couldn't read metadata: {}", reason),
+            Error::Field(name, ref value) => write!(f, "invalid {} {:?}", name, value),
+            Error::TooSmall(size) => write!(f, "{} bytes is too small for a header", size),
+            Error::OutsideCartridge { lma, size } => {
+                write!(f, "{} byte segment at {:#010x} is outside the image", size, lma)
+            }
+            Error::Overlap(lma) => write!(f, "segments overlap at {:#010x}", lma),
+            Error::Verify(error) => write!(f, "invalid header: {:?}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn description(&self) -> &str {
+        "cartridge error"
+    }
+}
+
+/// How to build the image.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Options {
+    /// The header to write, or `None` to keep the one already in the
+    /// ELF, e.g. from `gba-rt`'s `rom_header!`.
+    pub header: Option<Header>,
+
+    /// Offset of the code the header's entry branch jumps to.
+    pub entry: u32,
+
+    /// Pad the image with `0xFF` to a power of two bytes, as flash
+    /// carts and some emulators expect.
+    pub pad: bool,
+
+    /// Build a multiboot image, linked at `MULTIBOOT_START` by
+    /// `gba-rt`'s `multiboot` feature, for sending over the link cable.
+    /// It's padded with zeros to the size the BIOS sends, rather than
+    /// to a power of two.
+    pub multiboot: bool,
+}
+
+impl Default for Options {
+    /// Enters at 0xC0, where `gba-rt` puts `_init`.
+    fn default() -> Options {
+        Options {
+            header: None,
+            entry: header::SIZE as u32,
+            pad: false,
+            multiboot: false,
+        }
+    }
+}
+
+/// Builds a cartridge or multiboot image from a linked ELF file.
+pub fn build(elf: &[u8], options: &Options) -> Result<Vec<u8>, Error> {
+    let (image_start, max_size) = if options.multiboot {
+        (MULTIBOOT_START, MULTIBOOT_MAX_SIZE)
+    } else {
+        (CART_START, MAX_SIZE)
+    };
+    let segments = elf::segments(elf)?;
+    let mut rom = vec![0u8; header::SIZE];
+    let mut written: Vec<(usize, usize)> = Vec::new();
+    for segment in &segments {
+        let size = segment.data.len();
+        let outside = Error::OutsideCartridge { lma: segment.lma, size };
+        let start = segment.lma.checked_sub(image_start).ok_or(outside.clone())? as usize;
+        let end = start + size;
+        if end > max_size {
+            return Err(outside);
+        }
+        if written.iter().any(|&(s, e)| start < e && s < end) {
+            return Err(Error::Overlap(segment.lma));
+        }
+        written.push((start, end));
+
+        if rom.len() < end {
+            rom.resize(end, 0);
+        }
+        rom[start..end].copy_from_slice(segment.data);
+    }
+
+    if let Some(ref header) = options.header {
+        header.write(&mut rom, options.entry)?;
+    }
+    if options.multiboot {
+        let size = (rom.len() + MULTIBOOT_BLOCK - 1) & !(MULTIBOOT_BLOCK - 1);
+        rom.resize(size.max(MULTIBOOT_MIN_SIZE), 0);
+        if rom.len() > max_size {
+            return Err(Error::OutsideCartridge { lma: image_start, size: rom.len() });
+        }
+    } else if options.pad {
+        let size = rom.len().next_power_of_two();
+        rom.resize(size, 0xFF);
+    }
+    header::verify(&rom).map_err(Error::Verify)?;
+    Ok(rom)
+}
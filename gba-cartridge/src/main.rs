@@ -0,0 +1,85 @@
+extern crate gba_cartridge;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use gba_cartridge::{build, header, Options};
+use gba_cartridge::header::Header;
+
+const USAGE: &str = "\
+usage: gba-cartridge <elf> -o <rom> [options]
+       gba-cartridge --verify <rom>
+
+Without header options, the ELF's own header is kept.
+
+options:
+    --title <title>        up to 12 characters
+    --game-code <code>     4 characters
+    --maker-code <code>    2 characters
+    --version <n>          software version
+    --pad                  pad to a power of two bytes
+    --multiboot            build a multiboot image, linked into EWRAM";
+
+fn read(path: &str) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("gba-cartridge: {}", message);
+    process::exit(1);
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    let mut input = None;
+    let mut output = None;
+    let mut verify = None;
+    let mut options = Options::default();
+    let mut fields = Header::default();
+    let mut has_fields = false;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or(format!("{} needs a value", arg));
+        match &arg[..] {
+            "-o" => output = Some(value()?),
+            "--verify" => verify = Some(value()?),
+            "--title" => fields.title = value()?,
+            "--game-code" => fields.game_code = value()?,
+            "--maker-code" => fields.maker_code = value()?,
+            "--version" => fields.version = value()?.parse().map_err(|_| "invalid version")?,
+            "--pad" => options.pad = true,
+            "--multiboot" => options.multiboot = true,
+            _ if !arg.starts_with('-') && input.is_none() => input = Some(arg.clone()),
+            _ => return Err(USAGE.to_string()),
+        }
+        has_fields |= arg.starts_with("--") && arg != "--pad" && arg != "--multiboot" &&
+            arg != "--verify";
+    }
+    if has_fields {
+        options.header = Some(fields);
+    }
+
+    if let Some(path) = verify {
+        let rom = read(&path).map_err(|e| format!("{}: {}", path, e))?;
+        return header::verify(&rom).map_err(|e| format!("{}: invalid header: {:?}", path, e));
+    }
+    let (input, output) = match (input, output) {
+        (Some(input), Some(output)) => (input, output),
+        _ => return Err(USAGE.to_string()),
+    };
+    let elf = read(&input).map_err(|e| format!("{}: {}", input, e))?;
+    let rom = build(&elf, &options).map_err(|e| format!("{}: {}", input, e))?;
+    File::create(&output)
+        .and_then(|mut f| f.write_all(&rom))
+        .map_err(|e| format!("{}: {}", output, e))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(message) = run(&args) {
+        fail(&message);
+    }
+}
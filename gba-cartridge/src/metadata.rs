@@ -0,0 +1,150 @@
+//! Reads the header from the `[package.metadata.gba]` table of a
+//! game's `Cargo.toml`, so its build script can put a finished header
+//! in the ELF.
+//!
+//! ```toml
+//! [package.metadata.gba]
+//! title = "GBATEST"
+//! game-code = "AGTE"
+//! maker-code = "01"
+//! version = 0
+//! ```
+//!
+//! Missing keys keep their defaults.  The game's build script writes the
+//! header, checksum and all, to `$OUT_DIR/header.rs`:
+//!
+//! ```no_run
+//! extern crate gba_cartridge;
+//!
+//! fn main() {
+//!     gba_cartridge::metadata::write_header().unwrap();
+//! }
+//! ```
+//!
+//! and the game places it with `gba-rt`:
+//!
+//! ```ignore
+//! rom_header!(include!(concat!(env!("OUT_DIR"), "/header.rs")));
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_cartridge::metadata;
+//!
+//! let header = metadata::parse(r#"
+//! [package]
+//! name = "game"
+//!
+//! [package.metadata.gba]
+//! title = "GAME" # shown by flash carts
+//! version = 2
+//! "#).unwrap();
+//! assert_eq!("GAME", header.title);
+//! assert_eq!("0000", header.game_code);
+//! assert_eq!(2, header.version);
+//! ```
+
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use Error;
+use header::{self, Header};
+
+const TABLE: &str = "[package.metadata.gba]";
+
+fn string(key: &str, value: &str) -> Result<String, Error> {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        Ok(value[1..value.len() - 1].to_string())
+    } else {
+        Err(Error::Metadata(format!("`{}` should be a string, not `{}`", key, value)))
+    }
+}
+
+// Removes a trailing comment, leaving any `#` inside a string alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Reads the header from the text of a `Cargo.toml`.
+pub fn parse(manifest: &str) -> Result<Header, Error> {
+    let mut header = Header::default();
+    let mut in_table = false;
+    for line in manifest.lines() {
+        let line = strip_comment(line).trim();
+        if line.starts_with('[') {
+            in_table = line == TABLE;
+            continue;
+        }
+        if !in_table || line.is_empty() {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().unwrap().trim();
+        let value = parts.next()
+            .ok_or_else(|| Error::Metadata(format!("expected `key = value`, not `{}`", line)))?
+            .trim();
+        match key {
+            "title" => header.title = string(key, value)?,
+            "game-code" => header.game_code = string(key, value)?,
+            "maker-code" => header.maker_code = string(key, value)?,
+            "version" => {
+                header.version = value.parse().map_err(|_| {
+                    Error::Metadata(format!("`version` should be 0-255, not `{}`", value))
+                })?
+            }
+            _ => return Err(Error::Metadata(format!("unknown key `{}` in {}", key, TABLE))),
+        }
+    }
+    Ok(header)
+}
+
+/// The header as a Rust array expression, entering at 0xC0 where
+/// `gba-rt` puts `_init`.
+pub fn source(header: &Header) -> Result<String, Error> {
+    let bytes = header.bytes(header::SIZE as u32)?;
+    let mut source = String::from("[");
+    for (i, b) in bytes.iter().enumerate() {
+        if i % 12 == 0 {
+            source.push_str("\n    ");
+        } else {
+            source.push(' ');
+        }
+        write!(source, "0x{:02X},", b).unwrap();
+    }
+    source.push_str("\n]\n");
+    Ok(source)
+}
+
+/// For build scripts: reads the header from the crate's `Cargo.toml`
+/// and writes it to `$OUT_DIR/header.rs`.
+pub fn write_header() -> Result<(), Error> {
+    let io = |e: ::std::io::Error| Error::Metadata(e.to_string());
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| Error::Metadata("CARGO_MANIFEST_DIR isn't set".to_string()))?;
+    let out_dir = env::var("OUT_DIR")
+        .map_err(|_| Error::Metadata("OUT_DIR isn't set".to_string()))?;
+
+    let manifest_path = Path::new(&manifest_dir).join("Cargo.toml");
+    let mut manifest = String::new();
+    File::open(&manifest_path).and_then(|mut f| f.read_to_string(&mut manifest)).map_err(io)?;
+    let source = source(&parse(&manifest)?)?;
+    File::create(Path::new(&out_dir).join("header.rs"))
+        .and_then(|mut f| f.write_all(source.as_bytes()))
+        .map_err(io)?;
+
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+    Ok(())
+}
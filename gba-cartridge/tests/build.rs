@@ -0,0 +1,110 @@
+extern crate gba_cartridge;
+
+use gba_cartridge::header::{self, Header, VerifyError};
+use gba_cartridge::{build, elf, Error, Options, CART_START, MAX_SIZE};
+
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+
+fn put_u16(elf: &mut [u8], offset: usize, value: u16) {
+    elf[offset] = value as u8;
+    elf[offset + 1] = (value >> 8) as u8;
+}
+
+fn put_u32(elf: &mut [u8], offset: usize, value: u32) {
+    put_u16(elf, offset, value as u16);
+    put_u16(elf, offset + 2, (value >> 16) as u16);
+}
+
+// An ARM ELF file with a loadable segment at each `(lma, data)`.
+fn elf(segments: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut elf = vec![0u8; EHDR_SIZE + segments.len() * PHDR_SIZE];
+    elf[..6].copy_from_slice(b"\x7FELF\x01\x01");
+    put_u16(&mut elf, 18, 40);
+    put_u32(&mut elf, 28, EHDR_SIZE as u32);
+    put_u16(&mut elf, 42, PHDR_SIZE as u16);
+    put_u16(&mut elf, 44, segments.len() as u16);
+    for (i, &(lma, data)) in segments.iter().enumerate() {
+        let header = EHDR_SIZE + i * PHDR_SIZE;
+        let offset = elf.len() as u32;
+        put_u32(&mut elf, header, 1);
+        put_u32(&mut elf, header + 4, offset);
+        put_u32(&mut elf, header + 8, lma);
+        put_u32(&mut elf, header + 12, lma);
+        put_u32(&mut elf, header + 16, data.len() as u32);
+        elf.extend_from_slice(data);
+    }
+    elf
+}
+
+fn options() -> Options {
+    Options {
+        header: Some(Header {
+            title: "TEST".to_string(),
+            game_code: "ATST".to_string(),
+            maker_code: "01".to_string(),
+            version: 1,
+        }),
+        ..Options::default()
+    }
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let elf = elf(&[(CART_START, &[0; 0x100])]);
+    for len in 0..EHDR_SIZE {
+        assert!(elf::segments(&elf[..len]).is_err(), "{} bytes", len);
+    }
+    assert_eq!(Err(Error::Elf("not a 32-bit little-endian ELF file")),
+               elf::segments(b"\x7FELF"));
+}
+
+#[test]
+fn overlapping_segments_are_rejected() {
+    let elf = elf(&[(CART_START, &[0; 0x100]), (CART_START + 0xF0, &[0; 0x20])]);
+    assert_eq!(Err(Error::Overlap(CART_START + 0xF0)), build(&elf, &options()));
+
+    let elf = self::elf(&[(CART_START, &[0; 0x100]), (CART_START + 0x100, &[0; 0x20])]);
+    assert!(build(&elf, &options()).is_ok());
+}
+
+#[test]
+fn segments_outside_the_cartridge_are_rejected() {
+    let elf = elf(&[(0x0700_0000, &[0; 0x100])]);
+    assert_eq!(Err(Error::OutsideCartridge { lma: 0x0700_0000, size: 0x100 }),
+               build(&elf, &options()));
+
+    let lma = CART_START + MAX_SIZE as u32 - 4;
+    let elf = self::elf(&[(CART_START, &[0; 0x100]), (lma, &[0; 8])]);
+    assert_eq!(Err(Error::OutsideCartridge { lma, size: 8 }), build(&elf, &options()));
+}
+
+#[test]
+fn images_are_padded_to_a_power_of_two() {
+    let elf = elf(&[(CART_START, &[0x12; 0x300])]);
+    let rom = build(&elf, &options()).unwrap();
+    assert_eq!(0x300, rom.len());
+
+    let options = Options { pad: true, ..options() };
+    let rom = build(&elf, &options).unwrap();
+    assert_eq!(0x400, rom.len());
+    assert!(rom[header::SIZE..0x300].iter().all(|&b| b == 0x12));
+    assert!(rom[0x300..].iter().all(|&b| b == 0xFF));
+}
+
+#[test]
+fn headers_round_trip_through_verify() {
+    let elf = elf(&[(CART_START, &[0; 0x200])]);
+    let options = options();
+    let rom = build(&elf, &options).unwrap();
+    assert_eq!(Ok(()), header::verify(&rom));
+    assert_eq!(&options.header.as_ref().unwrap().bytes(options.entry).unwrap()[..],
+               &rom[..header::SIZE]);
+
+    // an ELF that already has the header builds without one
+    let elf = self::elf(&[(CART_START, &rom)]);
+    assert_eq!(Ok(rom), build(&elf, &Options::default()));
+
+    let elf = self::elf(&[(CART_START, &[0; 0x200])]);
+    assert_eq!(Err(Error::Verify(VerifyError::Entry)), build(&elf, &Options::default()));
+}
@@ -0,0 +1,81 @@
+//! Difference filters, as undone by `Diff8bitUnFilterWram`,
+//! `Diff8bitUnFilterVram` and `Diff16bitUnFilter`.
+//!
+//! After the header, the first unit is stored as-is and every later
+//! unit as its difference from the previous one.  Filtering doesn't
+//! make data smaller by itself, but smooth data such as gradients
+//! compresses much better afterwards.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_compress::diff;
+//!
+//! let data: Vec<u8> = (0..64).map(|i| i * 3).collect();
+//! let filtered = diff::filter8(&data).unwrap();
+//! assert!(filtered[5..data.len() + 4].iter().all(|&d| d == 3));
+//! assert_eq!(data, diff::unfilter(&filtered).unwrap());
+//!
+//! let filtered = diff::filter16(&data).unwrap();
+//! assert_eq!(data, diff::unfilter(&filtered).unwrap());
+//! ```
+
+use {header, expect_format, pad_to_word, Error, Format};
+
+/// Filters `data` in 8-bit units.
+pub fn filter8(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = header(Format::Diff8, data.len())?.to_vec();
+    let mut prev = 0u8;
+    for &byte in data {
+        out.push(byte.wrapping_sub(prev));
+        prev = byte;
+    }
+    pad_to_word(&mut out);
+    Ok(out)
+}
+
+/// Filters `data` in little-endian 16-bit units.
+pub fn filter16(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() & 1 != 0 {
+        return Err(Error::OddLength);
+    }
+    let mut out = header(Format::Diff16, data.len())?.to_vec();
+    let mut prev = 0u16;
+    for unit in data.chunks(2) {
+        let value = unit[0] as u16 | (unit[1] as u16) << 8;
+        let diff = value.wrapping_sub(prev);
+        out.push(diff as u8);
+        out.push((diff >> 8) as u8);
+        prev = value;
+    }
+    pad_to_word(&mut out);
+    Ok(out)
+}
+
+/// Undoes either filter as the BIOS would.
+pub fn unfilter(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let size = expect_format(data, &[Format::Diff8, Format::Diff16])?;
+    let body = &data[4..];
+    if body.len() < size {
+        return Err(Error::Truncated);
+    }
+    let mut out = Vec::with_capacity(size);
+    if data[0] == Format::Diff8.type_byte() {
+        let mut acc = 0u8;
+        for &diff in &body[..size] {
+            acc = acc.wrapping_add(diff);
+            out.push(acc);
+        }
+    } else {
+        if size & 1 != 0 {
+            return Err(Error::OddLength);
+        }
+        let mut acc = 0u16;
+        for unit in body[..size].chunks(2) {
+            acc = acc.wrapping_add(unit[0] as u16 | (unit[1] as u16) << 8);
+            out.push(acc as u8);
+            out.push((acc >> 8) as u8);
+        }
+    }
+    Ok(out)
+}
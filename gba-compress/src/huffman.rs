@@ -0,0 +1,261 @@
+//! Huffman coding, as decompressed by `HuffUnComp`.
+//!
+//! After the header comes a tree table: a size byte, the root node,
+//! then pairs of child nodes.  Each internal node holds a 6-bit offset
+//! to its pair of children and flags saying which children are leaves;
+//! leaves hold a symbol.  The bitstream follows as 32-bit words, read
+//! most significant bit first, with 0 taking the left child.
+//! Decoded symbols are packed into 32-bit words least significant
+//! first.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_compress::huffman::{self, SymbolSize};
+//!
+//! let data = b"mississippi, mississippi".to_vec();
+//! for &size in &[SymbolSize::Four, SymbolSize::Eight] {
+//!     let compressed = huffman::compress(&data, size).unwrap();
+//!     assert_eq!(data, huffman::decompress(&compressed).unwrap());
+//! }
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use {header, expect_format, pad_to_word, Error, Format};
+
+/// Width of each Huffman symbol.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SymbolSize {
+    /// Each byte is coded as two 4-bit symbols, low nibble first.
+    Four,
+
+    /// Each byte is coded as one 8-bit symbol.
+    Eight,
+}
+
+impl SymbolSize {
+    fn bits(&self) -> usize {
+        match *self {
+            SymbolSize::Four => 4,
+            SymbolSize::Eight => 8,
+        }
+    }
+}
+
+enum Node {
+    Leaf(u8),
+    Internal(usize, usize),
+}
+
+const MAX_OFFSET: isize = 63;
+
+fn symbols(data: &[u8], size: SymbolSize) -> Vec<u8> {
+    // The decoder writes whole words, so code enough zero padding to
+    // fill the last one.
+    let mut padded = data.to_vec();
+    pad_to_word(&mut padded);
+    match size {
+        SymbolSize::Four => padded.iter().flat_map(|&b| vec![b & 0xF, b >> 4]).collect(),
+        SymbolSize::Eight => padded,
+    }
+}
+
+// Builds a Huffman tree, returning the nodes and the root's index.
+fn build_tree(symbols: &[u8]) -> (Vec<Node>, usize) {
+    let mut counts = [0usize; 256];
+    for &symbol in symbols {
+        counts[symbol as usize] += 1;
+    }
+
+    let mut nodes = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for (symbol, &count) in counts.iter().enumerate() {
+        if count > 0 {
+            heap.push(Reverse((count, nodes.len())));
+            nodes.push(Node::Leaf(symbol as u8));
+        }
+    }
+    // The root must be an internal node, so make sure there are at
+    // least two leaves.
+    while heap.len() < 2 {
+        let symbol = if counts[0] == 0 { 0 } else { 1 };
+        counts[symbol] = 1;
+        heap.push(Reverse((0, nodes.len())));
+        nodes.push(Node::Leaf(symbol as u8));
+    }
+
+    while heap.len() > 1 {
+        let Reverse((left_count, left)) = heap.pop().unwrap();
+        let Reverse((right_count, right)) = heap.pop().unwrap();
+        heap.push(Reverse((left_count + right_count, nodes.len())));
+        nodes.push(Node::Internal(left, right));
+    }
+    let Reverse((_, root)) = heap.pop().unwrap();
+    (nodes, root)
+}
+
+// Lays out the tree table, returning the root byte and the child pairs.
+//
+// Children must come at most 64 pairs after their parent.  Pairs are
+// placed depth first to keep the number of waiting nodes small, except
+// when a waiting node is about to miss its deadline.
+fn layout(nodes: &[Node], root: usize) -> Result<(u8, Vec<[u8; 2]>), Error> {
+    let mut root_byte = 0u8;
+    let mut pairs: Vec<[u8; 2]> = Vec::new();
+    // (node, pair index, side) of internal nodes whose children
+    // haven't been placed yet.  The root lives at pair -1.
+    let mut pending: Vec<(usize, isize, usize)> = vec![(root, -1, 0)];
+
+    while !pending.is_empty() {
+        let next = pairs.len() as isize;
+        let mut deadlines = pending.iter()
+            .enumerate()
+            .map(|(i, &(_, pair, _))| (pair + 1 + MAX_OFFSET, i))
+            .collect::<Vec<_>>();
+        deadlines.sort();
+        if deadlines[0].0 < next {
+            return Err(Error::TreeLayout);
+        }
+        let tight = deadlines.iter()
+            .enumerate()
+            .any(|(k, &(deadline, _))| deadline <= next + k as isize);
+        let chosen = if tight { deadlines[0].1 } else { pending.len() - 1 };
+        let (node, pair, side) = pending.remove(chosen);
+
+        let (left, right) = match nodes[node] {
+            Node::Internal(left, right) => (left, right),
+            Node::Leaf(_) => unreachable!(),
+        };
+        let mut byte = (next - pair - 1) as u8;
+        let mut children = [0u8; 2];
+        for (child_side, &child) in [left, right].iter().enumerate() {
+            match nodes[child] {
+                Node::Leaf(symbol) => {
+                    children[child_side] = symbol;
+                    byte |= 0x80 >> child_side;
+                }
+                Node::Internal(..) => pending.push((child, next, child_side)),
+            }
+        }
+        pairs.push(children);
+        if pair < 0 {
+            root_byte = byte;
+        } else {
+            pairs[pair as usize][side] = byte;
+        }
+    }
+    Ok((root_byte, pairs))
+}
+
+fn codes(nodes: &[Node], node: usize, prefix: Vec<bool>, out: &mut Vec<Vec<bool>>) {
+    match nodes[node] {
+        Node::Leaf(symbol) => out[symbol as usize] = prefix,
+        Node::Internal(left, right) => {
+            let mut left_prefix = prefix.clone();
+            left_prefix.push(false);
+            codes(nodes, left, left_prefix, out);
+            let mut right_prefix = prefix;
+            right_prefix.push(true);
+            codes(nodes, right, right_prefix, out);
+        }
+    }
+}
+
+/// Compresses `data` with the given symbol size.
+pub fn compress(data: &[u8], size: SymbolSize) -> Result<Vec<u8>, Error> {
+    let format = match size {
+        SymbolSize::Four => Format::Huffman4,
+        SymbolSize::Eight => Format::Huffman8,
+    };
+    let mut out = header(format, data.len())?.to_vec();
+
+    let symbols = symbols(data, size);
+    let (nodes, root) = build_tree(&symbols);
+    let (root_byte, pairs) = layout(&nodes, root)?;
+
+    let mut table = vec![0, root_byte];
+    for pair in &pairs {
+        table.extend_from_slice(pair);
+    }
+    pad_to_word(&mut table);
+    table[0] = (table.len() / 2 - 1) as u8;
+    out.extend_from_slice(&table);
+
+    let mut code_table = vec![Vec::new(); 256];
+    codes(&nodes, root, Vec::new(), &mut code_table);
+    let mut word = 0u32;
+    let mut bits = 0;
+    for &symbol in &symbols {
+        for &bit in &code_table[symbol as usize] {
+            word |= (bit as u32) << (31 - bits);
+            bits += 1;
+            if bits == 32 {
+                out.extend_from_slice(&[word as u8, (word >> 8) as u8,
+                                        (word >> 16) as u8, (word >> 24) as u8]);
+                word = 0;
+                bits = 0;
+            }
+        }
+    }
+    if bits > 0 {
+        out.extend_from_slice(&[word as u8, (word >> 8) as u8,
+                                (word >> 16) as u8, (word >> 24) as u8]);
+    }
+    Ok(out)
+}
+
+/// Decompresses Huffman data as the BIOS would.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let size = expect_format(data, &[Format::Huffman4, Format::Huffman8])?;
+    let bits = if data[0] & 0xF == 4 { SymbolSize::Four } else { SymbolSize::Eight }.bits();
+    let byte_at = |addr: usize| data.get(addr).cloned().ok_or(Error::Truncated);
+
+    let tree_base = 5;
+    let root = byte_at(tree_base)?;
+    let mut input = 4 + (byte_at(4)? as usize + 1) * 2;
+
+    let mut out = Vec::with_capacity(size + 3);
+    let mut node_addr = tree_base;
+    let mut node = root;
+    let mut block = 0u32;
+    let mut block_bits = 0;
+    while out.len() < size {
+        let mut stream = 0u32;
+        for i in 0..4 {
+            stream |= (byte_at(input + i)? as u32) << (8 * i);
+        }
+        input += 4;
+        for _ in 0..32 {
+            if out.len() >= size {
+                break;
+            }
+            let next = (node_addr & !1) + (node & 0x3F) as usize * 2 + 2;
+            let (child, is_leaf) = if stream & 0x8000_0000 != 0 {
+                (next + 1, node & 0x40 != 0)
+            } else {
+                (next, node & 0x80 != 0)
+            };
+            stream <<= 1;
+            if !is_leaf {
+                node_addr = child;
+                node = byte_at(child)?;
+                continue;
+            }
+
+            let symbol = byte_at(child)? as u32 & ((1 << bits) - 1);
+            block |= symbol << block_bits;
+            block_bits += bits;
+            node_addr = tree_base;
+            node = root;
+            if block_bits == 32 {
+                out.extend_from_slice(&[block as u8, (block >> 8) as u8,
+                                        (block >> 16) as u8, (block >> 24) as u8]);
+                block = 0;
+                block_bits = 0;
+            }
+        }
+    }
+    out.truncate(size);
+    Ok(out)
+}
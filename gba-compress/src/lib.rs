@@ -0,0 +1,187 @@
+//! Compressors for the formats understood by the game boy advance
+//! BIOS decompression functions, for use from build scripts.
+//!
+//! Each format also has a decoder that mirrors the BIOS, so compressed
+//! assets can be checked on the host.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_compress::{decompress, lz77};
+//!
+//! let data = b"abracadabra, abracadabra, abracadabra";
+//! let compressed = lz77::compress(data).unwrap();
+//! assert!(compressed.len() < data.len());
+//! assert_eq!(&data[..], &decompress(&compressed).unwrap()[..]);
+//! ```
+
+use std::fmt;
+
+pub mod lz77;
+pub mod huffman;
+pub mod run_length;
+pub mod diff;
+
+/// Largest input size that fits in the 24-bit header size field.
+pub const MAX_SIZE: usize = 0xFF_FFFF;
+
+/// The data formats understood by the BIOS.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    /// LZ77, header type `0x10`.
+    Lz77,
+
+    /// Huffman with 4-bit symbols, header type `0x24`.
+    Huffman4,
+
+    /// Huffman with 8-bit symbols, header type `0x28`.
+    Huffman8,
+
+    /// Run length encoding, header type `0x30`.
+    RunLength,
+
+    /// 8-bit difference filter, header type `0x81`.
+    Diff8,
+
+    /// 16-bit difference filter, header type `0x82`.
+    Diff16,
+}
+
+impl Format {
+    /// The header type byte for this format.
+    pub fn type_byte(&self) -> u8 {
+        match *self {
+            Format::Lz77 => 0x10,
+            Format::Huffman4 => 0x24,
+            Format::Huffman8 => 0x28,
+            Format::RunLength => 0x30,
+            Format::Diff8 => 0x81,
+            Format::Diff16 => 0x82,
+        }
+    }
+
+    /// Looks up a format from its header type byte.
+    pub fn from_type_byte(byte: u8) -> Option<Format> {
+        match byte {
+            0x10 => Some(Format::Lz77),
+            0x24 => Some(Format::Huffman4),
+            0x28 => Some(Format::Huffman8),
+            0x30 => Some(Format::RunLength),
+            0x81 => Some(Format::Diff8),
+            0x82 => Some(Format::Diff16),
+            _ => None,
+        }
+    }
+}
+
+/// Errors from compressing or decompressing.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Error {
+    /// The input is larger than `MAX_SIZE`.
+    TooLarge(usize),
+
+    /// A 16-bit format was given an odd number of bytes.
+    OddLength,
+
+    /// The Huffman tree couldn't be laid out within the 6-bit child
+    /// offsets of the BIOS tree format.
+    TreeLayout,
+
+    /// The compressed data is shorter than its header or contents
+    /// require.
+    Truncated,
+
+    /// The header's type byte isn't one the BIOS understands.
+    UnknownFormat(u8),
+
+    /// The data is valid, but in a different format than expected.
+    WrongFormat(Format),
+
+    /// The compressed data would make the BIOS write past the size
+    /// in its header, or refers back before the start of the output.
+    Malformed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::TooLarge(size) => write!(f, "{} bytes is too large to compress", size),
+            Error::OddLength => write!(f, "16-bit data must have an even length"),
+            Error::TreeLayout => write!(f, "couldn't lay out huffman tree"),
+            Error::Truncated => write!(f, "compressed data is truncated"),
+            Error::UnknownFormat(byte) => write!(f, "unknown compression type {:#x}", byte),
+            Error::WrongFormat(format) => write!(f, "unexpected format {:?}", format),
+            Error::Malformed => write!(f, "compressed data is malformed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn description(&self) -> &str {
+        "compression error"
+    }
+}
+
+/// Builds the 4-byte header for `size` bytes of `format` data.
+pub fn header(format: Format, size: usize) -> Result<[u8; 4], Error> {
+    if size > MAX_SIZE {
+        return Err(Error::TooLarge(size));
+    }
+    Ok([format.type_byte(), size as u8, (size >> 8) as u8, (size >> 16) as u8])
+}
+
+/// Parses the header at the start of `data`, returning the format and
+/// decompressed size.
+pub fn parse_header(data: &[u8]) -> Result<(Format, usize), Error> {
+    if data.len() < 4 {
+        return Err(Error::Truncated);
+    }
+    let format = Format::from_type_byte(data[0]).ok_or(Error::UnknownFormat(data[0]))?;
+    let size = data[1] as usize | (data[2] as usize) << 8 | (data[3] as usize) << 16;
+    Ok((format, size))
+}
+
+fn expect_format(data: &[u8], formats: &[Format]) -> Result<usize, Error> {
+    let (format, size) = parse_header(data)?;
+    if formats.contains(&format) {
+        Ok(size)
+    } else {
+        Err(Error::WrongFormat(format))
+    }
+}
+
+// The BIOS requires compressed data to be word aligned and sized.
+fn pad_to_word(out: &mut Vec<u8>) {
+    while out.len() & 3 != 0 {
+        out.push(0);
+    }
+}
+
+/// Decompresses data in any format, as the matching BIOS function would.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    match parse_header(data)?.0 {
+        Format::Lz77 => lz77::decompress(data),
+        Format::Huffman4 | Format::Huffman8 => huffman::decompress(data),
+        Format::RunLength => run_length::decompress(data),
+        Format::Diff8 | Format::Diff16 => diff::unfilter(data),
+    }
+}
+
+/// Packs compressed bytes into little-endian words, as the BIOS
+/// functions expect to find them in ROM.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(vec![0x04030201, 0x00000005],
+///            gba_compress::to_words(&[1, 2, 3, 4, 5]));
+/// ```
+pub fn to_words(data: &[u8]) -> Vec<u32> {
+    data.chunks(4)
+        .map(|chunk| {
+            chunk.iter()
+                .enumerate()
+                .fold(0u32, |word, (i, &byte)| word | (byte as u32) << (8 * i))
+        })
+        .collect()
+}
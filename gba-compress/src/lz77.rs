@@ -0,0 +1,117 @@
+//! LZ77, as decompressed by `LZ77UnCompWram` and `LZ77UnCompVram`.
+//!
+//! After the header, data comes in groups of up to eight blocks, each
+//! preceded by a flag byte whose bits (most significant first) say
+//! whether the block is a literal byte (0) or a two byte
+//! back-reference (1).  A back-reference copies 3-18 bytes from 1-4096
+//! bytes back in the output.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_compress::lz77;
+//!
+//! let data = [7u8; 100];
+//! let compressed = lz77::compress_vram(&data).unwrap();
+//! assert_eq!(&data[..], &lz77::decompress(&compressed).unwrap()[..]);
+//! ```
+
+use {header, expect_format, pad_to_word, Error, Format};
+
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 18;
+const MAX_DISTANCE: usize = 4096;
+
+/// Compresses `data` for `LZ77UnCompWram`.
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    compress_with_min_distance(data, 1)
+}
+
+/// Compresses `data` so it is also safe for `LZ77UnCompVram`.
+///
+/// VRAM can only be written 16 bits at a time, so the BIOS buffers
+/// one byte before writing.  A back-reference with distance 1 would
+/// read that byte before it reaches VRAM, so these are never emitted.
+pub fn compress_vram(data: &[u8]) -> Result<Vec<u8>, Error> {
+    compress_with_min_distance(data, 2)
+}
+
+// Finds the longest match for `data[pos..]`, returning (length, distance).
+fn longest_match(data: &[u8], pos: usize, min_distance: usize) -> (usize, usize) {
+    let max_length = MAX_LENGTH.min(data.len() - pos);
+    let mut best = (0, 0);
+    if max_length < MIN_LENGTH || pos < min_distance {
+        return best;
+    }
+    for distance in min_distance..(MAX_DISTANCE.min(pos) + 1) {
+        let start = pos - distance;
+        let length = (0..max_length)
+            .take_while(|&i| data[start + i] == data[pos + i])
+            .count();
+        if length > best.0 {
+            best = (length, distance);
+            if length == max_length {
+                break;
+            }
+        }
+    }
+    best
+}
+
+fn compress_with_min_distance(data: &[u8], min_distance: usize) -> Result<Vec<u8>, Error> {
+    let mut out = header(Format::Lz77, data.len())?.to_vec();
+    let mut pos = 0;
+    while pos < data.len() {
+        let flag_index = out.len();
+        out.push(0);
+        for block in 0..8 {
+            if pos >= data.len() {
+                break;
+            }
+            let (length, distance) = longest_match(data, pos, min_distance);
+            if length >= MIN_LENGTH {
+                let encoded = ((length - MIN_LENGTH) << 12) | (distance - 1);
+                out.push((encoded >> 8) as u8);
+                out.push(encoded as u8);
+                out[flag_index] |= 0x80 >> block;
+                pos += length;
+            } else {
+                out.push(data[pos]);
+                pos += 1;
+            }
+        }
+    }
+    pad_to_word(&mut out);
+    Ok(out)
+}
+
+/// Decompresses LZ77 data as the BIOS would.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let size = expect_format(data, &[Format::Lz77])?;
+    let mut out = Vec::with_capacity(size);
+    let mut input = data[4..].iter().cloned();
+    let mut next = || input.next().ok_or(Error::Truncated);
+    while out.len() < size {
+        let flags = next()?;
+        for block in 0..8 {
+            if out.len() >= size {
+                break;
+            }
+            if flags & (0x80 >> block) == 0 {
+                out.push(next()?);
+                continue;
+            }
+            let encoded = (next()? as usize) << 8 | next()? as usize;
+            let length = (encoded >> 12) + MIN_LENGTH;
+            let distance = (encoded & 0xFFF) + 1;
+            if distance > out.len() || out.len() + length > size {
+                return Err(Error::Malformed);
+            }
+            for _ in 0..length {
+                let byte = out[out.len() - distance];
+                out.push(byte);
+            }
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,90 @@
+//! Run length encoding, as decompressed by `RLUnCompWram` and
+//! `RLUnCompVram`.
+//!
+//! After the header, each flag byte starts either a run (bit 7 set)
+//! of `(flag & 0x7F) + 3` copies of the next byte, or `(flag & 0x7F) + 1`
+//! literal bytes.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_compress::run_length;
+//!
+//! let mut data = vec![0u8; 200];
+//! data.extend_from_slice(b"some literal bytes");
+//! let compressed = run_length::compress(&data).unwrap();
+//! assert!(compressed.len() < 40);
+//! assert_eq!(data, run_length::decompress(&compressed).unwrap());
+//! ```
+
+use {header, expect_format, pad_to_word, Error, Format};
+
+const MIN_RUN: usize = 3;
+const MAX_RUN: usize = 0x7F + MIN_RUN;
+const MAX_LITERALS: usize = 0x80;
+
+fn run_length(data: &[u8], pos: usize) -> usize {
+    data[pos..].iter()
+        .take(MAX_RUN)
+        .take_while(|&&b| b == data[pos])
+        .count()
+}
+
+/// Compresses `data`.
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = header(Format::RunLength, data.len())?.to_vec();
+    let mut literals: Vec<u8> = Vec::new();
+    let mut pos = 0;
+    let flush = |literals: &mut Vec<u8>, out: &mut Vec<u8>| {
+        if !literals.is_empty() {
+            out.push((literals.len() - 1) as u8);
+            out.extend_from_slice(literals);
+            literals.clear();
+        }
+    };
+    while pos < data.len() {
+        let run = run_length(data, pos);
+        if run >= MIN_RUN {
+            flush(&mut literals, &mut out);
+            out.push(0x80 | (run - MIN_RUN) as u8);
+            out.push(data[pos]);
+            pos += run;
+        } else {
+            literals.push(data[pos]);
+            pos += 1;
+            if literals.len() == MAX_LITERALS {
+                flush(&mut literals, &mut out);
+            }
+        }
+    }
+    flush(&mut literals, &mut out);
+    pad_to_word(&mut out);
+    Ok(out)
+}
+
+/// Decompresses run length encoded data as the BIOS would.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let size = expect_format(data, &[Format::RunLength])?;
+    let mut out = Vec::with_capacity(size);
+    let mut input = data[4..].iter().cloned();
+    while out.len() < size {
+        let flag = input.next().ok_or(Error::Truncated)?;
+        let (length, run) = if flag & 0x80 != 0 {
+            ((flag & 0x7F) as usize + MIN_RUN, true)
+        } else {
+            ((flag & 0x7F) as usize + 1, false)
+        };
+        if out.len() + length > size {
+            return Err(Error::Malformed);
+        }
+        if run {
+            let byte = input.next().ok_or(Error::Truncated)?;
+            out.extend((0..length).map(|_| byte));
+        } else {
+            for _ in 0..length {
+                out.push(input.next().ok_or(Error::Truncated)?);
+            }
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,55 @@
+extern crate gba_compress;
+
+use gba_compress::lz77;
+
+// The distance of every back-reference in compressed LZ77 data.
+fn distances(compressed: &[u8]) -> Vec<usize> {
+    let size = gba_compress::parse_header(compressed).unwrap().1;
+    let mut distances = Vec::new();
+    let mut input = compressed[4..].iter().cloned();
+    let mut len = 0;
+    while len < size {
+        let flags = input.next().unwrap();
+        for block in 0..8 {
+            if len >= size {
+                break;
+            }
+            if flags & (0x80 >> block) == 0 {
+                input.next().unwrap();
+                len += 1;
+                continue;
+            }
+            let encoded = (input.next().unwrap() as usize) << 8 | input.next().unwrap() as usize;
+            distances.push((encoded & 0xFFF) + 1);
+            len += (encoded >> 12) + 3;
+        }
+    }
+    distances
+}
+
+fn samples() -> Vec<Vec<u8>> {
+    vec![
+        vec![7; 100],
+        vec![1, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 1, 2],
+        (0..600).map(|i| if i % 50 < 30 { 0 } else { (i % 7) as u8 }).collect(),
+        (0..5000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 29) as u8).collect(),
+    ]
+}
+
+#[test]
+fn vram_never_references_distance_1() {
+    for data in samples() {
+        let compressed = lz77::compress_vram(&data).unwrap();
+        let distances = distances(&compressed);
+        assert!(!distances.is_empty());
+        assert!(distances.iter().all(|&distance| distance >= 2), "{:?}", distances);
+        assert_eq!(data, lz77::decompress(&compressed).unwrap());
+    }
+}
+
+#[test]
+fn wram_references_distance_1() {
+    // so the test above would notice distance 1 references
+    let compressed = lz77::compress(&[7; 100]).unwrap();
+    assert!(distances(&compressed).contains(&1));
+}
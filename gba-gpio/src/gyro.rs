@@ -0,0 +1,71 @@
+use Pins;
+
+const START: u8 = 1;
+const CLOCK: u8 = 2;
+const DATA: u8 = 4;
+const MOTOR: u8 = 8;
+
+/// Bits clocked out of the sensor's converter for each reading.
+const READ_BITS: usize = 16;
+
+/// A gyro sensor, whose converter is started on pin 0, clocked on pin
+/// 1 and answers on pin 2, with a rumble motor on pin 3.
+///
+/// # Examples
+///
+/// ```
+/// use gba_gpio::Gyro;
+/// use gba_gpio::sim::SimGyro;
+///
+/// let mut gyro = Gyro::new(SimGyro::new(0x6C0));
+/// assert_eq!(0x6C0, gyro.read());
+///
+/// gyro.set_rumble(true);
+/// gyro.pins().set_rotation(0x7FF);
+/// assert_eq!(0x7FF, gyro.read());
+/// assert!(gyro.pins().rumble());
+/// ```
+pub struct Gyro<P> {
+    pins: P,
+    motor: u8,
+}
+
+impl<P: Pins> Gyro<P> {
+    /// Drives a sensor on `pins`, with the motor off.
+    pub fn new(mut pins: P) -> Gyro<P> {
+        pins.set_outputs(START | CLOCK | MOTOR);
+        pins.write(0);
+        Gyro { pins, motor: 0 }
+    }
+
+    /// The pins, e.g. to share them with another driver.
+    pub fn pins(&mut self) -> &mut P {
+        &mut self.pins
+    }
+
+    /// Gives back the pins.
+    pub fn release(self) -> P {
+        self.pins
+    }
+
+    /// Starts or stops the rumble motor.
+    pub fn set_rumble(&mut self, on: bool) {
+        self.motor = if on { MOTOR } else { 0 };
+        self.pins.write(self.motor);
+    }
+
+    /// Reads the rate of rotation, a 12-bit value that's about 0x6C0
+    /// when the console is still.
+    pub fn read(&mut self) -> u16 {
+        self.pins.write(self.motor | START);
+        self.pins.write(self.motor);
+        // each bit comes out as the clock falls, most significant first
+        let mut value = 0u16;
+        for _ in 0..READ_BITS {
+            self.pins.write(self.motor | CLOCK);
+            self.pins.write(self.motor);
+            value = value << 1 | ((self.pins.read() & DATA) >> 2) as u16;
+        }
+        value & 0xFFF
+    }
+}
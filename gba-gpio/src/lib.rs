@@ -0,0 +1,73 @@
+//! Drivers for the peripherals some cartridges wire to their GPIO
+//! port: a real-time clock, a rumble motor, a solar sensor and a gyro
+//! sensor.
+//!
+//! The port is four pins the game drives or reads one bit at a time,
+//! and every chip on it talks a bit-banged protocol.  The drivers are
+//! written against `Pins`, which `gba-rt` implements for the real
+//! port, and `sim` has simulated chips so the protocols can be tested
+//! on the host.
+//!
+//! Chips sharing a port, like the clock and solar sensor on the same
+//! cartridge, can each drive it through a `&mut` to the pins.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_gpio::{DateTime, Rtc};
+//! use gba_gpio::sim::SimRtc;
+//!
+//! let mut rtc = Rtc::new(SimRtc::new());
+//! if rtc.status().unwrap().power_failed {
+//!     rtc.reset();
+//! }
+//!
+//! rtc.set_date_time(&DateTime {
+//!     year: 24, month: 2, day: 28, weekday: 3,
+//!     hour: 23, minute: 59, second: 59,
+//! }).unwrap();
+//! rtc.pins().tick();
+//! assert_eq!(Ok(DateTime { year: 24, month: 2, day: 29, weekday: 4,
+//!                          hour: 0, minute: 0, second: 0 }),
+//!            rtc.date_time());
+//! ```
+
+#![no_std]
+
+mod gyro;
+mod rtc;
+mod rumble;
+pub mod sim;
+mod solar;
+
+pub use gyro::Gyro;
+pub use rtc::{Alarm, DateTime, Rtc, RtcError, Status, Time};
+pub use rumble::Rumble;
+pub use solar::SolarSensor;
+
+/// The four GPIO pins, as bits 0-3.
+pub trait Pins {
+    /// Makes the pins whose bits are set in `outputs` outputs, and the
+    /// rest inputs.
+    fn set_outputs(&mut self, outputs: u8);
+
+    /// Drives the output pins to the matching bits of `value`.
+    fn write(&mut self, value: u8);
+
+    /// The level of every pin, whether driven by the game or the chip.
+    fn read(&mut self) -> u8;
+}
+
+impl<P: Pins + ?Sized> Pins for &mut P {
+    fn set_outputs(&mut self, outputs: u8) {
+        (**self).set_outputs(outputs)
+    }
+
+    fn write(&mut self, value: u8) {
+        (**self).write(value)
+    }
+
+    fn read(&mut self) -> u8 {
+        (**self).read()
+    }
+}
@@ -0,0 +1,337 @@
+use Pins;
+
+const SCK: u8 = 1;
+const SIO: u8 = 2;
+const CS: u8 = 4;
+
+// Commands are 0110, then the register, then 1 to read it.
+pub(crate) const RESET: u8 = 0x60;
+pub(crate) const STATUS: u8 = 0x62;
+pub(crate) const DATE_TIME: u8 = 0x64;
+pub(crate) const TIME: u8 = 0x66;
+pub(crate) const ALARM: u8 = 0x68;
+pub(crate) const READ: u8 = 1;
+
+// Status register bits.
+pub(crate) const MINUTE_IRQ: u8 = 0x08;
+pub(crate) const ALARM_IRQ: u8 = 0x20;
+pub(crate) const HOURS_24: u8 = 0x40;
+pub(crate) const POWER_FAILED: u8 = 0x80;
+
+/// Set in hours after noon.
+pub(crate) const PM: u8 = 0x40;
+
+/// Set in alarm hours after noon.
+pub(crate) const ALARM_PM: u8 = 0x80;
+
+/// Errors from the real-time clock.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RtcError {
+    /// A field of a time being set is out of range.
+    OutOfRange,
+
+    /// The clock returned a time that isn't valid, e.g. because the
+    /// cartridge has no clock.
+    Invalid,
+}
+
+/// A date and time, in 24-hour time.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DateTime {
+    /// Years since 2000, 0-99.
+    pub year: u8,
+    /// 1-12.
+    pub month: u8,
+    /// 1-31.
+    pub day: u8,
+    /// 0-6, counted up at midnight; which day is 0 is up to the game.
+    pub weekday: u8,
+    /// 0-23.
+    pub hour: u8,
+    /// 0-59.
+    pub minute: u8,
+    /// 0-59.
+    pub second: u8,
+}
+
+/// A time of day, in 24-hour time.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Time {
+    /// 0-23.
+    pub hour: u8,
+    /// 0-59.
+    pub minute: u8,
+    /// 0-59.
+    pub second: u8,
+}
+
+/// When the alarm goes off, in 24-hour time.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Alarm {
+    /// 0-23.
+    pub hour: u8,
+    /// 0-59.
+    pub minute: u8,
+}
+
+/// The clock's status register.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Status {
+    /// The clock lost power, so its time is meaningless until `reset`.
+    /// Only the clock sets this.
+    pub power_failed: bool,
+
+    /// Interrupt for the first 30 seconds of every minute.
+    pub minute_irq: bool,
+
+    /// Interrupt while the time matches the alarm.
+    pub alarm_irq: bool,
+}
+
+pub(crate) fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+pub(crate) fn from_bcd(bcd: u8) -> u8 {
+    (bcd >> 4) * 10 + (bcd & 0xF)
+}
+
+fn encode(value: u8, min: u8, max: u8) -> Result<u8, RtcError> {
+    if value < min || value > max {
+        return Err(RtcError::OutOfRange);
+    }
+    Ok(to_bcd(value))
+}
+
+fn decode(bcd: u8, min: u8, max: u8) -> Result<u8, RtcError> {
+    let value = from_bcd(bcd);
+    if bcd >> 4 > 9 || bcd & 0xF > 9 || value < min || value > max {
+        return Err(RtcError::Invalid);
+    }
+    Ok(value)
+}
+
+fn encode_hour(hour: u8) -> Result<u8, RtcError> {
+    Ok(encode(hour, 0, 23)? | if hour >= 12 { PM } else { 0 })
+}
+
+fn decode_time(bytes: &[u8]) -> Result<Time, RtcError> {
+    Ok(Time {
+        hour: decode(bytes[0] & !PM, 0, 23)?,
+        minute: decode(bytes[1], 0, 59)?,
+        second: decode(bytes[2], 0, 59)?,
+    })
+}
+
+/// A Seiko S-3511 real-time clock, with its SCK, SIO and CS lines on
+/// pins 0-2.
+///
+/// The time is kept in 24-hour time; `new` switches the clock over if
+/// needed.  The clock interrupts through the cartridge IRQ line, which
+/// is the `EXTERNAL` interrupt source.
+///
+/// # Examples
+///
+/// ```
+/// use gba_gpio::{Alarm, Rtc, Time};
+/// use gba_gpio::sim::SimRtc;
+///
+/// let mut rtc = Rtc::new(SimRtc::new());
+/// rtc.reset();
+/// rtc.set_time(&Time { hour: 6, minute: 59, second: 59 }).unwrap();
+/// rtc.set_alarm(Some(&Alarm { hour: 7, minute: 0 })).unwrap();
+/// assert!(!rtc.pins().irq());
+///
+/// rtc.pins().tick();
+/// assert!(rtc.pins().irq());
+/// assert!(rtc.status().unwrap().alarm_irq);
+///
+/// rtc.set_alarm(None).unwrap();
+/// assert!(!rtc.pins().irq());
+/// ```
+pub struct Rtc<P> {
+    pins: P,
+}
+
+impl<P: Pins> Rtc<P> {
+    /// Drives a clock on `pins`, switching it to 24-hour time.
+    pub fn new(pins: P) -> Rtc<P> {
+        let mut rtc = Rtc { pins };
+        let status = rtc.read_status();
+        if status & HOURS_24 == 0 {
+            rtc.write_register(STATUS, &[status | HOURS_24]);
+        }
+        rtc
+    }
+
+    /// The pins, e.g. to share them with another driver.
+    pub fn pins(&mut self) -> &mut P {
+        &mut self.pins
+    }
+
+    /// Gives back the pins.
+    pub fn release(self) -> P {
+        self.pins
+    }
+
+    /// Resets every register, which is needed to clear
+    /// `Status::power_failed`.  The time goes back to midnight on
+    /// 2000-01-01 and interrupts are turned off.
+    pub fn reset(&mut self) {
+        self.write_register(RESET, &[]);
+        self.write_register(STATUS, &[HOURS_24]);
+    }
+
+    /// Reads the status register.
+    pub fn status(&mut self) -> Result<Status, RtcError> {
+        let status = self.read_status();
+        // nothing answering reads as all ones, which sets bits that are
+        // always clear
+        if status == 0xFF {
+            return Err(RtcError::Invalid);
+        }
+        Ok(Status {
+            power_failed: status & POWER_FAILED != 0,
+            minute_irq: status & MINUTE_IRQ != 0,
+            alarm_irq: status & ALARM_IRQ != 0,
+        })
+    }
+
+    /// Turns interrupts on or off.  `power_failed` is ignored.
+    pub fn set_status(&mut self, status: &Status) {
+        let mut bits = HOURS_24;
+        if status.minute_irq {
+            bits |= MINUTE_IRQ;
+        }
+        if status.alarm_irq {
+            bits |= ALARM_IRQ;
+        }
+        self.write_register(STATUS, &[bits]);
+    }
+
+    /// Reads the date and time.
+    pub fn date_time(&mut self) -> Result<DateTime, RtcError> {
+        let mut bytes = [0u8; 7];
+        self.read_register(DATE_TIME, &mut bytes);
+        let time = decode_time(&bytes[4..])?;
+        Ok(DateTime {
+            year: decode(bytes[0], 0, 99)?,
+            month: decode(bytes[1], 1, 12)?,
+            day: decode(bytes[2], 1, 31)?,
+            weekday: decode(bytes[3], 0, 6)?,
+            hour: time.hour,
+            minute: time.minute,
+            second: time.second,
+        })
+    }
+
+    /// Sets the date and time.
+    pub fn set_date_time(&mut self, date_time: &DateTime) -> Result<(), RtcError> {
+        let bytes = [encode(date_time.year, 0, 99)?,
+                     encode(date_time.month, 1, 12)?,
+                     encode(date_time.day, 1, 31)?,
+                     encode(date_time.weekday, 0, 6)?,
+                     encode_hour(date_time.hour)?,
+                     encode(date_time.minute, 0, 59)?,
+                     encode(date_time.second, 0, 59)?];
+        self.write_register(DATE_TIME, &bytes);
+        Ok(())
+    }
+
+    /// Reads just the time, which is quicker than the date and time.
+    pub fn time(&mut self) -> Result<Time, RtcError> {
+        let mut bytes = [0u8; 3];
+        self.read_register(TIME, &mut bytes);
+        decode_time(&bytes)
+    }
+
+    /// Sets just the time, leaving the date.
+    pub fn set_time(&mut self, time: &Time) -> Result<(), RtcError> {
+        let bytes = [encode_hour(time.hour)?,
+                     encode(time.minute, 0, 59)?,
+                     encode(time.second, 0, 59)?];
+        self.write_register(TIME, &bytes);
+        Ok(())
+    }
+
+    /// Sets the alarm and turns on its interrupt, or turns it off with
+    /// `None`.  Other interrupts are left alone.
+    pub fn set_alarm(&mut self, alarm: Option<&Alarm>) -> Result<(), RtcError> {
+        let status = self.read_status() & (MINUTE_IRQ | ALARM_IRQ) | HOURS_24;
+        match alarm {
+            Some(alarm) => {
+                let pm = if alarm.hour >= 12 { ALARM_PM } else { 0 };
+                let bytes = [encode(alarm.hour, 0, 23)? | pm, encode(alarm.minute, 0, 59)?];
+                // the alarm register is only there while its interrupt
+                // is on
+                self.write_register(STATUS, &[status | ALARM_IRQ]);
+                self.write_register(ALARM, &bytes);
+            }
+            None => self.write_register(STATUS, &[status & !ALARM_IRQ]),
+        }
+        Ok(())
+    }
+
+    fn read_status(&mut self) -> u8 {
+        let mut status = [0u8];
+        self.read_register(STATUS, &mut status);
+        status[0]
+    }
+
+    // Writes `value` a few times, as the clock needs each level held
+    // for longer than one write to the cartridge takes.
+    fn hold(&mut self, value: u8, writes: usize) {
+        for _ in 0..writes {
+            self.pins.write(value);
+        }
+    }
+
+    // Selects the clock and sends `command`, most significant bit first.
+    fn begin(&mut self, command: u8) {
+        self.pins.set_outputs(SCK | SIO | CS);
+        self.pins.write(SCK);
+        self.pins.write(SCK | CS);
+        for bit in (0..8).rev() {
+            self.send_bit(command >> bit & 1);
+        }
+    }
+
+    fn end(&mut self) {
+        self.hold(SCK, 2);
+    }
+
+    // Data is latched by the clock as SCK rises.
+    fn send_bit(&mut self, bit: u8) {
+        self.hold(CS | bit << 1, 3);
+        self.pins.write(CS | SCK | bit << 1);
+    }
+
+    fn receive_bit(&mut self) -> u8 {
+        self.hold(CS, 5);
+        self.pins.write(CS | SCK);
+        (self.pins.read() & SIO) >> 1
+    }
+
+    // Data is sent least significant bit first.
+    fn write_register(&mut self, command: u8, data: &[u8]) {
+        self.begin(command);
+        for &byte in data {
+            for bit in 0..8 {
+                self.send_bit(byte >> bit & 1);
+            }
+        }
+        self.end();
+    }
+
+    fn read_register(&mut self, command: u8, data: &mut [u8]) {
+        self.begin(command | READ);
+        self.pins.set_outputs(SCK | CS);
+        for byte in data.iter_mut() {
+            *byte = 0;
+            for bit in 0..8 {
+                *byte |= self.receive_bit() << bit;
+            }
+        }
+        self.end();
+    }
+}
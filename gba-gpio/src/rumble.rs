@@ -0,0 +1,65 @@
+use Pins;
+
+const MOTOR: u8 = 8;
+
+/// A rumble motor on pin 3.
+///
+/// # Examples
+///
+/// ```
+/// use gba_gpio::{Pins, Rumble};
+///
+/// // the motor just follows the pin
+/// #[derive(Default)]
+/// struct Motor {
+///     outputs: u8,
+///     pins: u8,
+/// }
+///
+/// impl Pins for Motor {
+///     fn set_outputs(&mut self, outputs: u8) {
+///         self.outputs = outputs;
+///     }
+///
+///     fn write(&mut self, value: u8) {
+///         self.pins = value & self.outputs;
+///     }
+///
+///     fn read(&mut self) -> u8 {
+///         self.pins
+///     }
+/// }
+///
+/// let mut rumble = Rumble::new(Motor::default());
+/// rumble.set(true);
+/// assert_eq!(8, rumble.pins().pins);
+/// rumble.set(false);
+/// assert_eq!(0, rumble.release().pins);
+/// ```
+pub struct Rumble<P> {
+    pins: P,
+}
+
+impl<P: Pins> Rumble<P> {
+    /// Drives a motor on `pins`, starting it off.
+    pub fn new(mut pins: P) -> Rumble<P> {
+        pins.set_outputs(MOTOR);
+        pins.write(0);
+        Rumble { pins }
+    }
+
+    /// The pins, e.g. to share them with another driver.
+    pub fn pins(&mut self) -> &mut P {
+        &mut self.pins
+    }
+
+    /// Gives back the pins.
+    pub fn release(self) -> P {
+        self.pins
+    }
+
+    /// Starts or stops the motor.
+    pub fn set(&mut self, on: bool) {
+        self.pins.write(if on { MOTOR } else { 0 });
+    }
+}
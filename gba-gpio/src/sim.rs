@@ -0,0 +1,394 @@
+//! Simulated chips, for testing the drivers on the host.
+//!
+//! Each implements `Pins`, answering the protocol the way the real chip
+//! does, and has methods to change what it measures or to inspect it.
+
+use Pins;
+use rtc::{self, from_bcd, to_bcd, DateTime};
+
+/// A simulated Seiko S-3511 real-time clock, for `Rtc`.
+///
+/// It starts as a clock whose battery has just been put in: power
+/// failed, at midnight on 2000-01-01, in 12-hour time.  Time only
+/// moves with `tick`.
+pub struct SimRtc {
+    outputs: u8,
+    pins: u8,
+    sio: u8,
+    status: u8,
+    now: DateTime,
+    alarm: [u8; 2],
+    transfer: Transfer,
+    buffer: [u8; 7],
+    len: usize,
+    index: usize,
+    bit: usize,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Transfer {
+    // waiting for the command
+    Command,
+    // sending a register to the game
+    Read,
+    // receiving this register from the game
+    Write(u8),
+    // done, or an unknown command
+    Ignore,
+}
+
+impl Default for SimRtc {
+    fn default() -> SimRtc {
+        SimRtc::new()
+    }
+}
+
+impl SimRtc {
+    /// A clock that has just been powered up.
+    pub fn new() -> SimRtc {
+        SimRtc {
+            outputs: 0,
+            pins: 0,
+            sio: 0,
+            status: rtc::POWER_FAILED,
+            now: SimRtc::midnight(),
+            alarm: [0; 2],
+            transfer: Transfer::Ignore,
+            buffer: [0; 7],
+            len: 0,
+            index: 0,
+            bit: 0,
+        }
+    }
+
+    fn midnight() -> DateTime {
+        DateTime { year: 0, month: 1, day: 1, weekday: 0, hour: 0, minute: 0, second: 0 }
+    }
+
+    /// The clock's current date and time.
+    pub fn date_time(&self) -> DateTime {
+        self.now
+    }
+
+    /// Moves the clock on a second.
+    pub fn tick(&mut self) {
+        let now = &mut self.now;
+        now.second += 1;
+        if now.second < 60 {
+            return;
+        }
+        now.second = 0;
+        now.minute += 1;
+        if now.minute < 60 {
+            return;
+        }
+        now.minute = 0;
+        now.hour += 1;
+        if now.hour < 24 {
+            return;
+        }
+        now.hour = 0;
+        now.weekday = (now.weekday + 1) % 7;
+        let days = match now.month {
+            2 if now.year & 3 == 0 => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        now.day += 1;
+        if now.day <= days {
+            return;
+        }
+        now.day = 1;
+        now.month += 1;
+        if now.month <= 12 {
+            return;
+        }
+        now.month = 1;
+        now.year = (now.year + 1) % 100;
+    }
+
+    /// Whether the clock is pulling the interrupt line.
+    pub fn irq(&self) -> bool {
+        let alarm = self.status & rtc::ALARM_IRQ != 0 &&
+            self.decode_hour(self.alarm[0] & !rtc::ALARM_PM, self.alarm[0] & rtc::ALARM_PM != 0) ==
+            self.now.hour && from_bcd(self.alarm[1]) == self.now.minute;
+        let minute = self.status & rtc::MINUTE_IRQ != 0 && self.now.second < 30;
+        alarm || minute
+    }
+
+    fn hours_24(&self) -> bool {
+        self.status & rtc::HOURS_24 != 0
+    }
+
+    fn encode_hour(&self, hour: u8) -> u8 {
+        let pm = if hour >= 12 { rtc::PM } else { 0 };
+        if self.hours_24() {
+            to_bcd(hour) | pm
+        } else {
+            to_bcd(hour % 12) | pm
+        }
+    }
+
+    fn decode_hour(&self, bcd: u8, pm: bool) -> u8 {
+        let hour = from_bcd(bcd);
+        if !self.hours_24() && pm {
+            hour + 12
+        } else {
+            hour
+        }
+    }
+
+    fn set_time(&mut self, bytes: &[u8]) {
+        self.now.hour = self.decode_hour(bytes[0] & !rtc::PM, bytes[0] & rtc::PM != 0);
+        self.now.minute = from_bcd(bytes[1]);
+        self.now.second = from_bcd(bytes[2]);
+    }
+
+    // Starts the transfer `command` asks for.
+    fn command(&mut self, command: u8) {
+        let register = command & !rtc::READ;
+        self.len = match register {
+            rtc::RESET => 0,
+            rtc::STATUS => 1,
+            rtc::DATE_TIME => 7,
+            rtc::TIME => 3,
+            rtc::ALARM => 2,
+            _ => {
+                self.transfer = Transfer::Ignore;
+                return;
+            }
+        };
+        self.index = 0;
+        self.bit = 0;
+        if command & rtc::READ == 0 {
+            self.buffer = [0; 7];
+            self.transfer = Transfer::Write(register);
+            if self.len == 0 {
+                self.store(register);
+            }
+            return;
+        }
+
+        self.transfer = Transfer::Read;
+        let now = self.now;
+        match register {
+            rtc::STATUS => self.buffer[0] = self.status,
+            rtc::DATE_TIME => {
+                self.buffer = [to_bcd(now.year), to_bcd(now.month), to_bcd(now.day),
+                               to_bcd(now.weekday), self.encode_hour(now.hour),
+                               to_bcd(now.minute), to_bcd(now.second)];
+            }
+            rtc::TIME => {
+                let time = [self.encode_hour(now.hour), to_bcd(now.minute), to_bcd(now.second)];
+                self.buffer[..3].copy_from_slice(&time);
+            }
+            rtc::ALARM => self.buffer[..2].copy_from_slice(&self.alarm),
+            _ => {}
+        }
+    }
+
+    // Stores a register the game has finished writing.
+    fn store(&mut self, register: u8) {
+        match register {
+            rtc::RESET => {
+                self.status = 0;
+                self.now = SimRtc::midnight();
+                self.alarm = [0; 2];
+            }
+            rtc::STATUS => {
+                let writable = rtc::MINUTE_IRQ | rtc::ALARM_IRQ | rtc::HOURS_24;
+                self.status = self.status & rtc::POWER_FAILED | self.buffer[0] & writable;
+            }
+            rtc::DATE_TIME => {
+                self.now.year = from_bcd(self.buffer[0]);
+                self.now.month = from_bcd(self.buffer[1]);
+                self.now.day = from_bcd(self.buffer[2]);
+                self.now.weekday = from_bcd(self.buffer[3]);
+                let time = [self.buffer[4], self.buffer[5], self.buffer[6]];
+                self.set_time(&time);
+            }
+            rtc::TIME => {
+                let time = [self.buffer[0], self.buffer[1], self.buffer[2]];
+                self.set_time(&time);
+            }
+            // the alarm register is only there while its interrupt is on
+            rtc::ALARM if self.status & rtc::ALARM_IRQ != 0 => {
+                self.alarm = [self.buffer[0], self.buffer[1]];
+            }
+            _ => {}
+        }
+        self.transfer = Transfer::Ignore;
+    }
+
+    // Handles SCK rising while the clock is selected.
+    fn clock(&mut self, sio: u8) {
+        match self.transfer {
+            Transfer::Command => {
+                self.buffer[0] = self.buffer[0] << 1 | sio;
+                self.bit += 1;
+                if self.bit == 8 {
+                    let command = self.buffer[0];
+                    if command >> 4 == 0x6 {
+                        self.command(command);
+                    } else {
+                        self.transfer = Transfer::Ignore;
+                    }
+                }
+            }
+            Transfer::Read => {
+                if self.index < self.len {
+                    self.sio = self.buffer[self.index] >> self.bit & 1;
+                    self.next_bit();
+                }
+            }
+            Transfer::Write(register) => {
+                self.buffer[self.index] |= sio << self.bit;
+                self.next_bit();
+                if self.index == self.len {
+                    self.store(register);
+                }
+            }
+            Transfer::Ignore => {}
+        }
+    }
+
+    fn next_bit(&mut self) {
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.index += 1;
+        }
+    }
+}
+
+const RTC_SCK: u8 = 1;
+const RTC_SIO: u8 = 2;
+const RTC_CS: u8 = 4;
+
+impl Pins for SimRtc {
+    fn set_outputs(&mut self, outputs: u8) {
+        self.outputs = outputs;
+    }
+
+    fn write(&mut self, value: u8) {
+        let old = self.pins;
+        self.pins = value & self.outputs;
+        if self.pins & RTC_CS == 0 {
+            self.transfer = Transfer::Ignore;
+            return;
+        }
+        if old & RTC_CS == 0 {
+            self.transfer = Transfer::Command;
+            self.buffer[0] = 0;
+            self.bit = 0;
+        }
+        if old & RTC_SCK == 0 && self.pins & RTC_SCK != 0 {
+            let sio = (self.pins & RTC_SIO) >> 1;
+            self.clock(sio);
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let sio = if self.outputs & RTC_SIO == 0 { self.sio << 1 } else { 0 };
+        self.pins | sio
+    }
+}
+
+const SOLAR_CLOCK: u8 = 1;
+const SOLAR_RESET: u8 = 2;
+const SOLAR_FLAG: u8 = 8;
+
+/// A simulated solar sensor, for `SolarSensor`.
+pub struct SimSolar {
+    outputs: u8,
+    pins: u8,
+    level: u8,
+    counter: u8,
+}
+
+impl SimSolar {
+    /// A sensor that reads `level`, lower for more light.
+    pub fn new(level: u8) -> SimSolar {
+        SimSolar { outputs: 0, pins: 0, level, counter: 0 }
+    }
+
+    /// Changes the light level.
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level;
+    }
+}
+
+impl Pins for SimSolar {
+    fn set_outputs(&mut self, outputs: u8) {
+        self.outputs = outputs;
+    }
+
+    fn write(&mut self, value: u8) {
+        let old = self.pins;
+        self.pins = value & self.outputs;
+        if self.pins & SOLAR_RESET != 0 {
+            self.counter = 0;
+        } else if old & SOLAR_CLOCK == 0 && self.pins & SOLAR_CLOCK != 0 {
+            self.counter = self.counter.saturating_add(1);
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let flag = if self.counter >= self.level { SOLAR_FLAG } else { 0 };
+        self.pins | flag & !self.outputs
+    }
+}
+
+const GYRO_START: u8 = 1;
+const GYRO_CLOCK: u8 = 2;
+const GYRO_DATA: u8 = 4;
+const GYRO_MOTOR: u8 = 8;
+
+/// A simulated gyro sensor and rumble motor, for `Gyro`.
+pub struct SimGyro {
+    outputs: u8,
+    pins: u8,
+    rotation: u16,
+    shift: u16,
+    data: u8,
+}
+
+impl SimGyro {
+    /// A sensor that reads `rotation`, a 12-bit value.
+    pub fn new(rotation: u16) -> SimGyro {
+        SimGyro { outputs: 0, pins: 0, rotation, shift: 0, data: 0 }
+    }
+
+    /// Changes the rate of rotation.
+    pub fn set_rotation(&mut self, rotation: u16) {
+        self.rotation = rotation;
+    }
+
+    /// Whether the rumble motor is running.
+    pub fn rumble(&self) -> bool {
+        self.pins & GYRO_MOTOR != 0
+    }
+}
+
+impl Pins for SimGyro {
+    fn set_outputs(&mut self, outputs: u8) {
+        self.outputs = outputs;
+    }
+
+    fn write(&mut self, value: u8) {
+        let old = self.pins;
+        self.pins = value & self.outputs;
+        if self.pins & GYRO_START != 0 {
+            self.shift = self.rotation & 0xFFF;
+        }
+        if old & GYRO_CLOCK != 0 && self.pins & GYRO_CLOCK == 0 {
+            self.data = (self.shift >> 15) as u8;
+            self.shift <<= 1;
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        self.pins | (self.data << 2) & GYRO_DATA & !self.outputs
+    }
+}
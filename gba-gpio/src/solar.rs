@@ -0,0 +1,65 @@
+use Pins;
+
+const CLOCK: u8 = 1;
+const RESET: u8 = 2;
+/// Shared with the real-time clock's CS, so kept low.
+const CS: u8 = 4;
+const FLAG: u8 = 8;
+
+/// A solar sensor, clocked on pin 0, reset on pin 1 and answering on
+/// pin 3.
+///
+/// The sensor compares the light level against a counter the game
+/// clocks up from 0.  Pin 2 must stay low, as it selects the real-time
+/// clock that shares the port.
+///
+/// # Examples
+///
+/// ```
+/// use gba_gpio::SolarSensor;
+/// use gba_gpio::sim::SimSolar;
+///
+/// let mut sensor = SolarSensor::new(SimSolar::new(0xE8));
+/// assert_eq!(0xE8, sensor.read());
+///
+/// // the sun comes out
+/// sensor.pins().set_level(0x60);
+/// assert_eq!(0x60, sensor.read());
+/// ```
+pub struct SolarSensor<P> {
+    pins: P,
+}
+
+impl<P: Pins> SolarSensor<P> {
+    /// Drives a sensor on `pins`.
+    pub fn new(mut pins: P) -> SolarSensor<P> {
+        pins.set_outputs(CLOCK | RESET | CS);
+        pins.write(0);
+        SolarSensor { pins }
+    }
+
+    /// The pins, e.g. to share them with another driver.
+    pub fn pins(&mut self) -> &mut P {
+        &mut self.pins
+    }
+
+    /// Gives back the pins.
+    pub fn release(self) -> P {
+        self.pins
+    }
+
+    /// Measures the light level.  Lower readings mean more light, from
+    /// about 0xE8 in the dark to 0x50 in full sun.
+    pub fn read(&mut self) -> u8 {
+        self.pins.set_outputs(CLOCK | RESET | CS);
+        self.pins.write(RESET);
+        self.pins.write(0);
+        let mut count = 0;
+        while count < 0xFF && self.pins.read() & FLAG == 0 {
+            self.pins.write(CLOCK);
+            self.pins.write(0);
+            count += 1;
+        }
+        count
+    }
+}
@@ -0,0 +1,247 @@
+//! A first-fit heap whose blocks can grow and shrink in place.
+//!
+//! Free memory is kept as a list of holes sorted by address, each
+//! starting with its size and the address of the next one.  Blocks and
+//! holes are whole units of two words, the room a hole needs for that,
+//! so whatever is left over when a block is carved out of a hole, or
+//! cut off the end of a shrinking block, can always become a hole
+//! itself, and nothing is lost.
+//!
+//! `gba-rt` runs the game's heap on it, in EWRAM.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_heap::{block_size, UNIT};
+//!
+//! let mut memory = [0usize; 64];
+//! let mut heap = gba_heap::EMPTY;
+//! unsafe {
+//!     heap.init(memory.as_mut_ptr() as usize, 64 * std::mem::size_of::<usize>());
+//!     let total = heap.available();
+//!
+//!     let a = heap.allocate(2 * UNIT, 4);
+//!     let b = heap.allocate(UNIT, 4);
+//!     assert_eq!(total - 3 * UNIT, heap.available());
+//!
+//!     // with `b` gone, `a` can grow into where it was
+//!     heap.deallocate(b, UNIT);
+//!     assert!(heap.grow_in_place(a, 2 * UNIT, 3 * UNIT));
+//!     assert_eq!(3 * UNIT, block_size(3 * UNIT - 1));
+//!
+//!     heap.shrink_in_place(a, 3 * UNIT, 1);
+//!     heap.deallocate(a, 1);
+//!     assert_eq!(total, heap.available());
+//! }
+//! ```
+
+#![no_std]
+
+use core::{mem, ptr};
+
+/// Blocks are whole units, each big enough to hold a hole's size and
+/// link.
+pub const UNIT: usize = 2 * mem::size_of::<usize>();
+
+/// A heap with no memory, for statics that are given it later with
+/// `init`.
+pub const EMPTY: Heap = Heap { first: 0 };
+
+/// The size the heap really sets aside for `size` bytes, in whole
+/// units.  Blocks can be freed or resized with any size that gives the
+/// same block size.
+pub fn block_size(size: usize) -> usize {
+    if size == 0 {
+        UNIT
+    } else {
+        size.saturating_add(UNIT - 1) & !(UNIT - 1)
+    }
+}
+
+// `address` rounded up to `align`, or `None` past the end of memory.
+fn align_up(address: usize, align: usize) -> Option<usize> {
+    address.checked_add(align - 1).map(|address| address & !(align - 1))
+}
+
+// The size of the hole at `hole`.
+unsafe fn hole_size(hole: usize) -> usize {
+    ptr::read(hole as *const usize)
+}
+
+// The address of the hole after `hole`, or 0 if it's the last.
+unsafe fn hole_next(hole: usize) -> usize {
+    ptr::read((hole as *const usize).offset(1))
+}
+
+unsafe fn write_hole(hole: usize, size: usize, next: usize) {
+    ptr::write(hole as *mut usize, size);
+    ptr::write((hole as *mut usize).offset(1), next);
+}
+
+/// The heap.
+pub struct Heap {
+    // the lowest hole, or 0 when there are none
+    first: usize,
+}
+
+impl Heap {
+    /// Hands the heap the `size` bytes at `start`, which it owns from
+    /// then on.  The ends are trimmed to whole units.
+    ///
+    /// # Safety
+    ///
+    /// The memory must be valid, and used by nothing else for as long
+    /// as the heap is.
+    pub unsafe fn init(&mut self, start: usize, size: usize) {
+        let end = (start + size) & !(UNIT - 1);
+        self.first = 0;
+        let start = match align_up(start, UNIT) {
+            Some(start) => start,
+            None => return,
+        };
+        if end > start {
+            write_hole(start, end - start, 0);
+            self.first = start;
+        }
+    }
+
+    // Points the hole before `next`, or the heap if `prev` is 0, at it.
+    unsafe fn link(&mut self, prev: usize, next: usize) {
+        if prev == 0 {
+            self.first = next;
+        } else {
+            ptr::write((prev as *mut usize).offset(1), next);
+        }
+    }
+
+    /// Bytes free, in all the holes together.
+    pub fn available(&self) -> usize {
+        let mut available = 0;
+        let mut hole = self.first;
+        while hole != 0 {
+            unsafe {
+                available += hole_size(hole);
+                hole = hole_next(hole);
+            }
+        }
+        available
+    }
+
+    /// Allocates `size` bytes aligned to `align`, a power of two, from
+    /// the first hole they fit in, or returns null if none has room.
+    ///
+    /// # Safety
+    ///
+    /// The memory given to `init` must still be the heap's.
+    pub unsafe fn allocate(&mut self, size: usize, align: usize) -> *mut u8 {
+        let size = block_size(size);
+        let mut prev = 0;
+        let mut hole = self.first;
+        while hole != 0 {
+            let (hole_end, next) = (hole + hole_size(hole), hole_next(hole));
+            // holes are whole units, so aligning can only leave whole
+            // units before the block, and a hole that can't be aligned
+            // is too near the end of memory for any after it to be
+            let start = match align_up(hole, align) {
+                Some(start) => start,
+                None => break,
+            };
+            match start.checked_add(size) {
+                Some(end) if end <= hole_end => {
+                    let next = if end < hole_end {
+                        write_hole(end, hole_end - end, next);
+                        end
+                    } else {
+                        next
+                    };
+                    if start > hole {
+                        write_hole(hole, start - hole, next);
+                    } else {
+                        self.link(prev, next);
+                    }
+                    return start as *mut u8;
+                }
+                _ => {}
+            }
+            prev = hole;
+            hole = next;
+        }
+        ptr::null_mut()
+    }
+
+    /// Frees the `size` byte block at `ptr`, merging it with the holes
+    /// either side.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a block from this heap, and `size` must give the
+    /// same block size as it was allocated or last resized with.
+    pub unsafe fn deallocate(&mut self, ptr: *mut u8, size: usize) {
+        let start = ptr as usize;
+        let mut size = block_size(size);
+        let mut prev = 0;
+        let mut next = self.first;
+        while next != 0 && next < start {
+            prev = next;
+            next = hole_next(next);
+        }
+
+        if next == start + size {
+            size += hole_size(next);
+            next = hole_next(next);
+        }
+        if prev != 0 && prev + hole_size(prev) == start {
+            write_hole(prev, hole_size(prev) + size, next);
+        } else {
+            write_hole(start, size, next);
+            self.link(prev, start);
+        }
+    }
+
+    /// Grows the `size` byte block at `ptr` to `new_size` bytes into
+    /// the hole right after it, returning whether there was room.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a block from this heap, and `size` must give the
+    /// same block size as it was allocated or last resized with.
+    pub unsafe fn grow_in_place(&mut self, ptr: *mut u8, size: usize, new_size: usize) -> bool {
+        let (old, new) = (block_size(size), block_size(new_size));
+        if new <= old {
+            return true;
+        }
+        let end = ptr as usize + old;
+        let mut prev = 0;
+        let mut hole = self.first;
+        while hole != 0 && hole < end {
+            prev = hole;
+            hole = hole_next(hole);
+        }
+        if hole != end || hole_size(hole) < new - old {
+            return false;
+        }
+
+        let (rest, next) = (hole_size(hole) - (new - old), hole_next(hole));
+        if rest > 0 {
+            write_hole(end + new - old, rest, next);
+            self.link(prev, end + new - old);
+        } else {
+            self.link(prev, next);
+        }
+        true
+    }
+
+    /// Shrinks the `size` byte block at `ptr` to `new_size` bytes,
+    /// freeing the units it no longer needs.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a block from this heap, and `size` must give the
+    /// same block size as it was allocated or last resized with.
+    pub unsafe fn shrink_in_place(&mut self, ptr: *mut u8, size: usize, new_size: usize) {
+        let (old, new) = (block_size(size), block_size(new_size));
+        if new < old {
+            self.deallocate((ptr as usize + new) as *mut u8, old - new);
+        }
+    }
+}
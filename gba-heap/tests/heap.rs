@@ -0,0 +1,123 @@
+extern crate gba_heap;
+
+use std::mem;
+
+use gba_heap::{Heap, UNIT};
+
+const WORDS: usize = 256;
+
+// A heap over `memory`, which must outlive it.
+fn heap(memory: &mut [usize; WORDS]) -> Heap {
+    let mut heap = gba_heap::EMPTY;
+    unsafe { heap.init(memory.as_mut_ptr() as usize, WORDS * mem::size_of::<usize>()) };
+    heap
+}
+
+fn offset(a: *mut u8, b: *mut u8) -> usize {
+    b as usize - a as usize
+}
+
+#[test]
+fn shrinking_frees_the_whole_tail() {
+    let mut memory = [0; WORDS];
+    let mut heap = heap(&mut memory);
+    let total = heap.available();
+    unsafe {
+        let block = heap.allocate(8 * UNIT, 4);
+        // including by less than a unit, which only frees anything once
+        // the shrinks add up to one
+        let mut size = 8 * UNIT;
+        for &new_size in &[8 * UNIT - 1, 7 * UNIT + 1, 5 * UNIT, 3 * UNIT - 3, 1] {
+            heap.shrink_in_place(block, size, new_size);
+            size = new_size;
+            assert_eq!(total - gba_heap::block_size(size), heap.available());
+        }
+        heap.deallocate(block, size);
+    }
+    assert_eq!(total, heap.available());
+    // and it's one hole again
+    unsafe { assert!(!heap.allocate(total, 4).is_null()) };
+}
+
+#[test]
+fn blocks_grow_into_the_hole_after_them() {
+    let mut memory = [0; WORDS];
+    let mut heap = heap(&mut memory);
+    let total = heap.available();
+    unsafe {
+        let a = heap.allocate(2 * UNIT, 4);
+        let b = heap.allocate(2 * UNIT, 4);
+        let c = heap.allocate(UNIT, 4);
+        assert_eq!(2 * UNIT, offset(a, b));
+        assert_eq!(2 * UNIT, offset(b, c));
+        assert!(!heap.grow_in_place(a, 2 * UNIT, 2 * UNIT + 1));
+
+        heap.deallocate(b, 2 * UNIT);
+        assert!(heap.grow_in_place(a, 2 * UNIT, 3 * UNIT));
+        // what's left of the hole is still there
+        assert_eq!(offset(a, c) - UNIT, offset(a, heap.allocate(1, 4)));
+        assert!(!heap.grow_in_place(a, 3 * UNIT, 3 * UNIT + 1));
+
+        // and the end of the heap is one big hole
+        let available = heap.available();
+        assert!(heap.grow_in_place(c, UNIT, UNIT + available));
+        assert_eq!(0, heap.available());
+        assert!(!heap.grow_in_place(c, UNIT + available, UNIT + available + 1));
+        heap.shrink_in_place(c, UNIT + available, UNIT);
+        assert_eq!(available, heap.available());
+    }
+    assert_eq!(total - 5 * UNIT, heap.available());
+}
+
+#[test]
+fn freed_blocks_merge() {
+    let mut memory = [0; WORDS];
+    let mut heap = heap(&mut memory);
+    let total = heap.available();
+    unsafe {
+        let quarter = (total / 4) & !(UNIT - 1);
+        let sizes = [quarter, quarter, quarter, total - 3 * quarter];
+        let blocks: Vec<_> = sizes.iter().map(|&size| heap.allocate(size, 4)).collect();
+        assert!(blocks.iter().all(|block| !block.is_null()));
+        assert!(heap.allocate(1, 4).is_null());
+        for &i in &[1, 3, 0, 2] {
+            heap.deallocate(blocks[i], sizes[i]);
+        }
+        assert!(!heap.allocate(total, 4).is_null());
+    }
+}
+
+#[test]
+fn blocks_are_aligned() {
+    let mut memory = [0; WORDS];
+    let mut heap = heap(&mut memory);
+    let total = heap.available();
+    unsafe {
+        let small = heap.allocate(1, 1);
+        let aligned = heap.allocate(UNIT, 16 * UNIT);
+        assert_eq!(0, aligned as usize % (16 * UNIT));
+        // the gap it skipped is still free
+        let gap = heap.allocate(UNIT, 4);
+        assert!(gap > small && gap < aligned);
+        for &(block, size) in &[(small, 1), (aligned, UNIT), (gap, UNIT)] {
+            heap.deallocate(block, size);
+        }
+    }
+    assert_eq!(total, heap.available());
+}
+
+#[test]
+fn running_out_returns_null() {
+    let mut memory = [0; WORDS];
+    let mut heap = heap(&mut memory);
+    let total = heap.available();
+    unsafe {
+        assert!(heap.allocate(total + 1, 4).is_null());
+        assert!(heap.allocate(!0, 4).is_null());
+        // alignments no hole can meet
+        assert!(heap.allocate(1, 1 << (mem::size_of::<usize>() * 8 - 1)).is_null());
+        let mut empty = gba_heap::EMPTY;
+        assert!(empty.allocate(1, 4).is_null());
+    }
+    assert_eq!(total, heap.available());
+}
@@ -10,6 +10,11 @@ extern crate gba_register_generator;
 extern crate quote;
 
 mod video;
+mod timers;
+mod dma;
+mod keypad;
+mod sio;
+mod system;
 
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
@@ -17,6 +22,11 @@ fn main() {
     let mut f = File::create(&dest_path).unwrap();
 
     let video_tokens = video::tokens();
+    let timers_tokens = timers::tokens();
+    let dma_tokens = dma::tokens();
+    let keypad_tokens = keypad::tokens();
+    let sio_tokens = sio::tokens();
+    let system_tokens = system::tokens();
 
     f.write_all(format!(
         "{}",
@@ -25,6 +35,21 @@ fn main() {
                 pub mod video {
                     #video_tokens
                 }
+                pub mod timers {
+                    #timers_tokens
+                }
+                pub mod dma {
+                    #dma_tokens
+                }
+                pub mod keypad {
+                    #keypad_tokens
+                }
+                pub mod sio {
+                    #sio_tokens
+                }
+                pub mod system {
+                    #system_tokens
+                }
             }
         }).as_bytes()).unwrap();
 }
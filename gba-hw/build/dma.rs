@@ -0,0 +1,161 @@
+use quote::Tokens;
+use syn::Ident;
+use gba_register_generator::*;
+
+pub fn tokens() -> Tokens {
+    let registers = [
+        RegisterBuilder::default()
+            .title(Ident::new("DmaControl"))
+            .doc("Controls a DMA channel.")
+            .repr(Ident::new("u16"))
+            .fields(vec![
+                FieldBuilder::default()
+                    .name(Ident::new("dest_control"))
+                    .doc("How the destination address changes after each unit.")
+                    .start(5usize)
+                    .size(2usize)
+                    .format(Format::Enum{
+                        title: Ident::new("DestControl"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("Increment")
+                                .doc("Increment after each unit")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Decrement")
+                                .doc("Decrement after each unit")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Fixed")
+                                .doc("Always write to the same address")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("IncrementReload")
+                                .doc("Increment, resetting to the original address on each repeat")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("source_control"))
+                    .doc("How the source address changes after each unit.")
+                    .start(7usize)
+                    .size(2usize)
+                    .format(Format::Enum{
+                        title: Ident::new("SourceControl"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("Increment")
+                                .doc("Increment after each unit")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Decrement")
+                                .doc("Decrement after each unit")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Fixed")
+                                .doc("Always read from the same address")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("repeat"))
+                    .doc("Restart the transfer at every start event rather than once.")
+                    .start(9usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: None,
+                        quick_set_false: None,
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("unit_size"))
+                    .doc("Size of each transferred unit.")
+                    .start(10usize)
+                    .size(1usize)
+                    .format(Format::Enum{
+                        title: Ident::new("UnitSize"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("HalfWord")
+                                .doc("16-bit units")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Word")
+                                .doc("32-bit units")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("game_pak_drq"))
+                    .doc("Let the cartridge request transfers.  DMA 3 only.")
+                    .start(11usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: None,
+                        quick_set_false: None,
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("start_timing"))
+                    .doc("When the transfer starts.")
+                    .start(12usize)
+                    .size(2usize)
+                    .format(Format::Enum{
+                        title: Ident::new("StartTiming"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("Immediately")
+                                .doc("As soon as the channel is enabled")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("VBlank")
+                                .doc("At the start of vertical blank")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("HBlank")
+                                .doc("At the start of horizontal blank")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Special")
+                                .doc("Sound FIFO for DMA 1 and 2, video capture for DMA 3")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("complete_irq"))
+                    .doc("Fire the channel's interrupt when the transfer completes.")
+                    .start(14usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_complete_irq")),
+                        quick_set_false: Some(Ident::new("disable_complete_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("enabled"))
+                    .doc("Enables the channel.  Cleared by hardware when a
+non-repeating transfer completes.")
+                    .start(15usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable")),
+                        quick_set_false: Some(Ident::new("disable")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+            ])
+            .build().unwrap(),
+    ];
+    generate_registers(&registers)
+}
@@ -0,0 +1,68 @@
+use quote::Tokens;
+use syn::Ident;
+use std::rc::Rc;
+use gba_register_generator::*;
+
+pub fn tokens() -> Tokens {
+    let registers = [
+        RegisterBuilder::default()
+            .title(Ident::new("KeyControl"))
+            .doc("Controls the keypad interrupt.")
+            .repr(Ident::new("u16"))
+            .fields(vec![
+                FieldBuilder::default()
+                    .name(Ident::new("keys"))
+                    .doc("Keys that take part in the interrupt condition")
+                    .start(0usize)
+                    .size(10usize)
+                    .access(Access::ReadWrite)
+                    .format(Format::Type {
+                        type_ident: Ident::new("::keypad::KeySet"),
+                        as_bits: Some(Rc::new(|ts| {
+                            quote! {
+                                #ts.bits()
+                            }
+                        })),
+                        from_bits: Some(Rc::new(|ts| {
+                            quote! {
+                                ::keypad::KeySet::from_bits(#ts).unwrap()
+                            }
+                        })),
+                    })
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("irq"))
+                    .doc("Fire the `KEYPAD` interrupt when the condition is met.")
+                    .start(14usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_irq")),
+                        quick_set_false: Some(Ident::new("disable_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("condition"))
+                    .doc("How `keys` are combined.")
+                    .start(15usize)
+                    .size(1usize)
+                    .format(Format::Enum{
+                        title: Ident::new("KeyCondition"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("AnyPressed")
+                                .doc("Fire when any of the keys is pressed")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("AllPressed")
+                                .doc("Fire when all of the keys are pressed")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+            ])
+            .build().unwrap(),
+    ];
+    generate_registers(&registers)
+}
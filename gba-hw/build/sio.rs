@@ -0,0 +1,141 @@
+use quote::Tokens;
+use syn::Ident;
+use gba_register_generator::*;
+
+fn bool_field(name: &str, doc: &str, start: usize, access: Access) -> Field {
+    FieldBuilder::default()
+        .name(Ident::new(name))
+        .doc(doc)
+        .start(start)
+        .size(1usize)
+        .format(Format::Bool {
+            quick_set_true: None,
+            quick_set_false: None,
+        })
+        .access(access)
+        .build().unwrap()
+}
+
+pub fn tokens() -> Tokens {
+    let registers = [
+        RegisterBuilder::default()
+            .title(Ident::new("SioControl"))
+            .doc("Controls the serial port.  The meaning of the low bits depends on `mode`.")
+            .repr(Ident::new("u16"))
+            .fields(vec![
+                bool_field("internal_clock",
+                           "Normal mode: be the master, driving the clock.",
+                           0, Access::ReadWrite),
+                bool_field("clock_2mhz",
+                           "Normal mode: the internal clock is 2MHz rather than 256kHz.",
+                           1, Access::ReadWrite),
+                bool_field("si_high",
+                           "Normal mode: the other machine's SO, high when nothing is connected.",
+                           2, Access::ReadOnly),
+                bool_field("so_high",
+                           "Normal mode: drive SO high rather than low between transfers.",
+                           3, Access::ReadWrite),
+                FieldBuilder::default()
+                    .name(Ident::new("baud_rate"))
+                    .doc("Multi-player mode: the transfer rate, which every machine must share.")
+                    .start(0usize)
+                    .size(2usize)
+                    .format(Format::Enum{
+                        title: Ident::new("BaudRate"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("Baud9600")
+                                .doc("9600 bits per second")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Baud38400")
+                                .doc("38400 bits per second")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Baud57600")
+                                .doc("57600 bits per second")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Baud115200")
+                                .doc("115200 bits per second")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                bool_field("child",
+                           "Multi-player mode: this machine is a child rather than the parent.",
+                           2, Access::ReadOnly),
+                bool_field("all_ready",
+                           "Multi-player mode: every machine is connected and ready.",
+                           3, Access::ReadOnly),
+                FieldBuilder::default()
+                    .name(Ident::new("multi_id"))
+                    .doc("Multi-player mode: 0 for the parent, 1-3 for the children.")
+                    .start(4usize)
+                    .size(2usize)
+                    .format(Format::Type {
+                        type_ident: Ident::new("u8"),
+                        as_bits: None,
+                        from_bits: None,
+                    })
+                    .access(Access::ReadOnly)
+                    .build().unwrap(),
+                bool_field("multi_error",
+                           "Multi-player mode: the last transfer failed.",
+                           6, Access::ReadOnly),
+                FieldBuilder::default()
+                    .name(Ident::new("active"))
+                    .doc("Starts a transfer.  Cleared by hardware when it completes.")
+                    .start(7usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("start")),
+                        quick_set_false: Some(Ident::new("stop")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("mode"))
+                    .doc("The serial mode, when `mode_select` picks the serial modes.")
+                    .start(12usize)
+                    .size(2usize)
+                    .format(Format::Enum{
+                        title: Ident::new("SioMode"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("Normal8")
+                                .doc("Normal mode, 8 bits per transfer")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Normal32")
+                                .doc("Normal mode, 32 bits per transfer")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("MultiPlayer")
+                                .doc("Multi-player mode, 16 bits from each machine per transfer")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Uart")
+                                .doc("UART mode")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("irq"))
+                    .doc("Fire the `SIO` interrupt when a transfer completes.")
+                    .start(14usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_irq")),
+                        quick_set_false: Some(Ident::new("disable_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+            ])
+            .build().unwrap(),
+    ];
+    generate_registers(&registers)
+}
@@ -0,0 +1,80 @@
+use quote::Tokens;
+use syn::Ident;
+use std::rc::Rc;
+use gba_register_generator::*;
+
+fn access_time(name: &str, doc: &str, start: usize) -> Field {
+    FieldBuilder::default()
+        .name(Ident::new(name))
+        .doc(doc)
+        .start(start)
+        .size(2usize)
+        .access(Access::ReadWrite)
+        .format(Format::Type {
+            type_ident: Ident::new("::system::AccessTime"),
+            as_bits: Some(Rc::new(|ts| {
+                quote! {
+                    (#ts as u16)
+                }
+            })),
+            from_bits: Some(Rc::new(|ts| {
+                quote! {
+                    ::system::AccessTime::from_bits(#ts)
+                }
+            })),
+        })
+        .build().unwrap()
+}
+
+fn fast_second(name: &str, doc: &str, start: usize) -> Field {
+    FieldBuilder::default()
+        .name(Ident::new(name))
+        .doc(doc)
+        .start(start)
+        .size(1usize)
+        .format(Format::Bool {
+            quick_set_true: None,
+            quick_set_false: None,
+        })
+        .access(Access::ReadWrite)
+        .build().unwrap()
+}
+
+pub fn tokens() -> Tokens {
+    let registers = [
+        RegisterBuilder::default()
+            .title(Ident::new("WaitControl"))
+            .doc("Controls the wait states for cartridge ROM and save memory.")
+            .repr(Ident::new("u16"))
+            .fields(vec![
+                access_time("sram", "Access time for SRAM and flash.", 0),
+                access_time("ws0_first", "First access time for ROM at 0x08000000.", 2),
+                fast_second("ws0_fast_second",
+                            "Sequential ROM accesses at 0x08000000 take 1 cycle rather than 2.",
+                            4),
+                access_time("ws1_first", "First access time for ROM at 0x0A000000.", 5),
+                fast_second("ws1_fast_second",
+                            "Sequential ROM accesses at 0x0A000000 take 1 cycle rather than 4.",
+                            7),
+                access_time("ws2_first",
+                            "First access time for ROM and EEPROM at 0x0C000000.",
+                            8),
+                fast_second("ws2_fast_second",
+                            "Sequential ROM accesses at 0x0C000000 take 1 cycle rather than 8.",
+                            10),
+                FieldBuilder::default()
+                    .name(Ident::new("prefetch"))
+                    .doc("Prefetch ROM while the CPU isn't using the bus.")
+                    .start(14usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_prefetch")),
+                        quick_set_false: Some(Ident::new("disable_prefetch")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+            ])
+            .build().unwrap(),
+    ];
+    generate_registers(&registers)
+}
@@ -0,0 +1,79 @@
+use quote::Tokens;
+use syn::Ident;
+use gba_register_generator::*;
+
+pub fn tokens() -> Tokens {
+    let registers = [
+        RegisterBuilder::default()
+            .title(Ident::new("TimerControl"))
+            .doc("Controls the rate and behavior of a timer.")
+            .repr(Ident::new("u16"))
+            .fields(vec![
+                FieldBuilder::default()
+                    .name(Ident::new("prescaler"))
+                    .doc("Number of CPU cycles per tick.  Ignored when `cascade` is set.")
+                    .start(0usize)
+                    .size(2usize)
+                    .format(Format::Enum{
+                        title: Ident::new("Prescaler"),
+                        variants: vec![
+                            VariantBuilder::default()
+                                .title("Cycles1")
+                                .doc("One tick per cycle (16.78MHz)")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Cycles64")
+                                .doc("One tick per 64 cycles (262.21kHz)")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Cycles256")
+                                .doc("One tick per 256 cycles (65.536kHz)")
+                                .build().unwrap(),
+                            VariantBuilder::default()
+                                .title("Cycles1024")
+                                .doc("One tick per 1024 cycles (16.384kHz)")
+                                .build().unwrap(),
+                        ]
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("cascade"))
+                    .doc("Tick once each time the previous timer overflows.
+Has no effect on timer 0.")
+                    .start(2usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: None,
+                        quick_set_false: None,
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("overflow_irq"))
+                    .doc("Fire the timer's interrupt on overflow.")
+                    .start(6usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_overflow_irq")),
+                        quick_set_false: Some(Ident::new("disable_overflow_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("running"))
+                    .doc("Starts or stops the timer.  Starting a stopped timer
+reloads the counter from the reload value.")
+                    .start(7usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("start")),
+                        quick_set_false: Some(Ident::new("stop")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+            ])
+            .build().unwrap(),
+    ];
+    generate_registers(&registers)
+}
@@ -120,10 +120,92 @@ Enabling this will reduce th number of visible sprites.")
                     })
                     .build().unwrap(),
             ])
-            .build().unwrap()
+            .build().unwrap(),
+        RegisterBuilder::default()
+            .title(Ident::new("DisplayStatus"))
+            .doc("Display status and interrupt control.")
+            .repr(Ident::new("u16"))
+            .fields(vec![
+                FieldBuilder::default()
+                    .name(Ident::new("in_vblank"))
+                    .doc("Set during lines 160-226, but not line 227.")
+                    .start(0usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: None,
+                        quick_set_false: None,
+                    })
+                    .access(Access::ReadOnly)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("in_hblank"))
+                    .doc("Set during the horizontal blank of every line.")
+                    .start(1usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: None,
+                        quick_set_false: None,
+                    })
+                    .access(Access::ReadOnly)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("vcounter_match"))
+                    .doc("Set while `vcount` matches `vcount_setting`.")
+                    .start(2usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: None,
+                        quick_set_false: None,
+                    })
+                    .access(Access::ReadOnly)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("vblank_irq"))
+                    .doc("Fire the `VBLANK` interrupt when entering vertical blank.")
+                    .start(3usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_vblank_irq")),
+                        quick_set_false: Some(Ident::new("disable_vblank_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("hblank_irq"))
+                    .doc("Fire the `HBLANK` interrupt when entering horizontal blank.")
+                    .start(4usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_hblank_irq")),
+                        quick_set_false: Some(Ident::new("disable_hblank_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("vcounter_irq"))
+                    .doc("Fire the `VCOUNTER` interrupt when `vcount` matches `vcount_setting`.")
+                    .start(5usize)
+                    .size(1usize)
+                    .format(Format::Bool {
+                        quick_set_true: Some(Ident::new("enable_vcounter_irq")),
+                        quick_set_false: Some(Ident::new("disable_vcounter_irq")),
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+                FieldBuilder::default()
+                    .name(Ident::new("vcount_setting"))
+                    .doc("Line to compare `vcount` against.")
+                    .start(8usize)
+                    .size(8usize)
+                    .format(Format::Type {
+                        type_ident: Ident::new("u8"),
+                        as_bits: None,
+                        from_bits: None,
+                    })
+                    .access(Access::ReadWrite)
+                    .build().unwrap(),
+            ])
+            .build().unwrap(),
     ];
-    let register_tokens = registers.iter().map(|t| generate_register(t));
-    quote! {
-        #(#register_tokens)*
-    }
+    generate_registers(&registers)
 }
\ No newline at end of file
@@ -0,0 +1,37 @@
+pub use ::gen::dma::*;
+use ::WriteOnly;
+
+/// `Channel` is the register block for a single DMA channel.
+#[repr(C)]
+pub struct Channel {
+    /// Address to transfer from.
+    pub source: WriteOnly<u32>,
+
+    /// Address to transfer to.
+    pub dest: WriteOnly<u32>,
+
+    /// Number of units to transfer.  0 means the maximum
+    /// (0x4000 for channels 0-2, 0x10000 for channel 3).
+    pub count: WriteOnly<u16>,
+
+    /// Channel settings.  Enabling the channel starts the transfer.
+    pub control: DmaControl,
+}
+
+register!(
+    /// `channel0` is the highest priority channel, and may only
+    /// access internal memory.
+    pub channel0: Channel => 0x400_00B0);
+
+register!(
+    /// `channel1` is normally used to feed sound FIFO A.
+    pub channel1: Channel => 0x400_00BC);
+
+register!(
+    /// `channel2` is normally used to feed sound FIFO B.
+    pub channel2: Channel => 0x400_00C8);
+
+register!(
+    /// `channel3` is the general purpose channel, and the only one
+    /// that may write to the cartridge.
+    pub channel3: Channel => 0x400_00D4);
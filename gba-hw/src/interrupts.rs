@@ -101,8 +101,24 @@ register!(
     /// `irq_acknowledge_bios` is the special interrupt acknowledge
     /// register for the BIOS.
     ///
-    /// Interrupt handlers should write to this register if the
-    /// application uses any of the `wait..` functions from the bios.
+    /// Interrupt handlers should set the sources they handled in this
+    /// register if the application uses any of the `wait..` functions
+    /// from the bios.
     ///
-    /// usage is identical to `irq_acknowledge`.
-    pub irq_acknowledge_bios: WriteOnly<SourceSet> => 0x0300_7FF8);
+    /// Unlike `irq_acknowledge`, this is plain memory that the BIOS
+    /// clears as it consumes flags, so sources must be added to it
+    /// rather than written over it; otherwise flags set earlier in the
+    /// same interrupt, or by an interrupted handler, are lost.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use gba_hw::interrupts;
+    /// // indicate that we have handled VBLANK
+    /// unsafe {
+    ///     let bios = interrupts::irq_acknowledge_bios();
+    ///     let flags = bios.read();
+    ///     bios.write(flags | interrupts::VBLANK);
+    /// }
+    /// ```
+    pub irq_acknowledge_bios: ReadWrite<SourceSet> => 0x0300_7FF8);
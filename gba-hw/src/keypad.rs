@@ -0,0 +1,35 @@
+/// `KeySet` represents a collection of keys
+bitflags! {
+    pub flags KeySet: u16 {
+        const A         = 1 << 0,
+        const B         = 1 << 1,
+        const SELECT    = 1 << 2,
+        const START     = 1 << 3,
+        const RIGHT     = 1 << 4,
+        const LEFT      = 1 << 5,
+        const UP        = 1 << 6,
+        const DOWN      = 1 << 7,
+        const R         = 1 << 8,
+        const L         = 1 << 9,
+    }
+}
+
+pub use ::gen::keypad::*;
+use ::ReadOnly;
+
+register!(
+    /// `key_input` is the raw keypad state.
+    ///
+    /// Note that bits are *cleared* while the key is held, so
+    /// use `pressed` for a `KeySet` of held keys.
+    pub key_input: ReadOnly<u16> => 0x400_0130);
+
+register!(
+    /// `key_control` controls the keypad interrupt.
+    pub key_control: KeyControl => 0x400_0132);
+
+/// Returns the set of keys currently held down.
+#[inline(always)]
+pub unsafe fn pressed() -> KeySet {
+    KeySet::from_bits_truncate(!key_input().read())
+}
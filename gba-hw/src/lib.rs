@@ -21,3 +21,9 @@ include!(concat!(env!("OUT_DIR"), "/gen.rs"));
 
 pub mod interrupts;
 pub mod video;
+pub mod timers;
+pub mod dma;
+pub mod keypad;
+pub mod sio;
+pub mod sound;
+pub mod system;
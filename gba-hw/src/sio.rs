@@ -0,0 +1,25 @@
+pub use ::gen::sio::*;
+use ::{ReadOnly, ReadWrite};
+
+register!(
+    /// `data32` is the data register for 32-bit normal mode.
+    pub data32: ReadWrite<u32> => 0x400_0120);
+
+register!(
+    /// `multi_data` holds the values received from each machine in
+    /// multi-player mode, with the parent at index 0.
+    pub multi_data: [ReadOnly<u16>; 4] => 0x400_0120);
+
+register!(
+    /// `control` is the serial control register.
+    pub control: SioControl => 0x400_0128);
+
+register!(
+    /// `send_data` is the value sent in multi-player mode, or the
+    /// data register in 8-bit normal mode.
+    pub send_data: ReadWrite<u16> => 0x400_012A);
+
+register!(
+    /// `mode_select` chooses between the serial modes and general
+    /// purpose mode.  Write 0 for normal and multi-player modes.
+    pub mode_select: ReadWrite<u16> => 0x400_0134);
@@ -0,0 +1,31 @@
+use ::{ReadWrite, WriteOnly};
+
+register!(
+    /// `psg_control` sets the volume and stereo routing of the four
+    /// tone generator channels.
+    pub psg_control: ReadWrite<u16> => 0x400_0080);
+
+register!(
+    /// `mix_control` sets the volume, routing and timers of the two
+    /// direct sound channels, and the overall tone generator volume.
+    pub mix_control: ReadWrite<u16> => 0x400_0082);
+
+register!(
+    /// `master_control` enables sound (bit 7) and reports which tone
+    /// generator channels are playing (bits 0-3).
+    ///
+    /// Note that the other sound registers can't be written while
+    /// sound is disabled.
+    pub master_control: ReadWrite<u16> => 0x400_0084);
+
+register!(
+    /// `bias` sets the output bias level and the PWM resolution.
+    pub bias: ReadWrite<u16> => 0x400_0088);
+
+register!(
+    /// `fifo_a` queues four 8-bit samples for direct sound channel A.
+    pub fifo_a: WriteOnly<u32> => 0x400_00A0);
+
+register!(
+    /// `fifo_b` queues four 8-bit samples for direct sound channel B.
+    pub fifo_b: WriteOnly<u32> => 0x400_00A4);
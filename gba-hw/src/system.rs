@@ -0,0 +1,27 @@
+pub use ::gen::system::*;
+
+/// Cycles taken by the first access to a region of the cartridge.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum AccessTime {
+    Cycles4 = 0,
+    Cycles3 = 1,
+    Cycles2 = 2,
+    Cycles8 = 3,
+}
+
+impl AccessTime {
+    #[inline(always)]
+    pub fn from_bits(bits: u16) -> AccessTime {
+        match bits & 3 {
+            0 => AccessTime::Cycles4,
+            1 => AccessTime::Cycles3,
+            2 => AccessTime::Cycles2,
+            _ => AccessTime::Cycles8,
+        }
+    }
+}
+
+register!(
+    /// `wait_control` sets the cartridge wait states.  SRAM and flash
+    /// need `AccessTime::Cycles8` for `sram`.
+    pub wait_control: WaitControl => 0x400_0204);
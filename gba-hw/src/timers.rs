@@ -0,0 +1,29 @@
+pub use ::gen::timers::*;
+use ::ReadWrite;
+
+/// `Timer` is the register block for a single timer.
+#[repr(C)]
+pub struct Timer {
+    /// Reading returns the current count.  Writing sets the value
+    /// loaded on overflow and when the timer is started.
+    pub counter: ReadWrite<u16>,
+
+    /// Timer settings.
+    pub control: TimerControl,
+}
+
+register!(
+    /// `timer0` is timer 0, which may drive the sound FIFOs.
+    pub timer0: Timer => 0x400_0100);
+
+register!(
+    /// `timer1` is timer 1, which may drive the sound FIFOs.
+    pub timer1: Timer => 0x400_0104);
+
+register!(
+    /// `timer2` is timer 2.
+    pub timer2: Timer => 0x400_0108);
+
+register!(
+    /// `timer3` is timer 3.
+    pub timer3: Timer => 0x400_010C);
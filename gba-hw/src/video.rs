@@ -19,8 +19,48 @@ bitflags! {
 }
 
 pub use ::gen::video::*;
+use ::{ReadOnly, WriteOnly};
 
 register!(
     /// `display_control` controls high-level settings for the LCD
     /// display controller.
     pub display_control: DisplayControl => 0x400_0000);
+
+register!(
+    /// `display_status` reports blanking state and controls the
+    /// display interrupts.
+    pub display_status: DisplayStatus => 0x400_0004);
+
+register!(
+    /// `vcount` is the line currently being drawn, from 0 to 227.
+    ///
+    /// Lines 160 and above are in vertical blank.
+    pub vcount: ReadOnly<u16> => 0x400_0006);
+
+/// `BackgroundAffine` is the rotation/scaling register block of an
+/// affine background.
+///
+/// The layout matches the output of the BIOS `BgAffineSet` call.
+#[repr(C)]
+pub struct BackgroundAffine {
+    /// Texture x change per screen pixel (8.8 fixed point)
+    pub pa: WriteOnly<i16>,
+    /// Texture x change per screen line (8.8 fixed point)
+    pub pb: WriteOnly<i16>,
+    /// Texture y change per screen pixel (8.8 fixed point)
+    pub pc: WriteOnly<i16>,
+    /// Texture y change per screen line (8.8 fixed point)
+    pub pd: WriteOnly<i16>,
+    /// Texture x at the top left of the screen (20.8 fixed point)
+    pub x: WriteOnly<i32>,
+    /// Texture y at the top left of the screen (20.8 fixed point)
+    pub y: WriteOnly<i32>,
+}
+
+register!(
+    /// `bg2_affine` controls background 2 in video modes 1 to 5.
+    pub bg2_affine: BackgroundAffine => 0x400_0020);
+
+register!(
+    /// `bg3_affine` controls background 3 in video mode 2.
+    pub bg3_affine: BackgroundAffine => 0x400_0030);
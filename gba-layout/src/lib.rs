@@ -0,0 +1,581 @@
+//! A typed model of the game's memory layout, from which the linker
+//! script and section loading code are generated.
+//!
+//! `gba-rt`'s build script uses it, so it's a crate of its own to be
+//! tested on the host.
+//!
+//! The layout is read from the file named by the `GBA_RT_LAYOUT`
+//! environment variable, if set.  Each line is `key = value`, with `#`
+//! starting a comment:
+//!
+//! ```text
+//! # bytes of IRQ stack, below the BIOS stacks at 0x03007FA0
+//! irq_stack = 0xA0
+//! # minimum bytes of user stack, below the IRQ stack
+//! usr_stack = 1K
+//! # where the heap lives, `ewram` or `iwram`
+//! heap = ewram
+//! # reserve = <name> <memory> <size> [<address>]
+//! reserve = mixer_buffer iwram 0x630
+//! # section = <.name> <memory>
+//! section = .sprites ewram
+//! ```
+//!
+//! Reserved regions without an address are stacked downwards from the
+//! top of their memory (below the stacks, in IWRAM), and get
+//! `__<name>_start` and `__<name>_end` symbols.  Sections and the
+//! heap must fit below the lowest reserved region in their memory, so
+//! regions with an address are best kept near the top.  Extra sections
+//! are loaded from the cartridge by startup, like `.iwram` and
+//! `.ewram`.
+//!
+//! With the `multiboot` feature, the image is linked into EWRAM, where
+//! the BIOS loads it, instead of the cartridge.  Only IWRAM sections
+//! need copying, and the image must fit in 256KiB.
+
+mod symbols;
+pub use symbols::unused_section_symbols;
+
+use std::fmt::Write;
+use std::fs::File;
+use std::io::Read;
+
+/// Work RAM that sections, the heap and reserved regions can go in.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Memory {
+    Ewram,
+    Iwram,
+}
+
+impl Memory {
+    pub fn origin(&self) -> u32 {
+        match *self {
+            Memory::Ewram => 0x0200_0000,
+            Memory::Iwram => 0x0300_0000,
+        }
+    }
+
+    pub fn length(&self) -> u32 {
+        match *self {
+            Memory::Ewram => 0x4_0000,
+            Memory::Iwram => 0x8000,
+        }
+    }
+
+    /// The name of the memory in the linker script.
+    pub fn region(&self) -> &'static str {
+        match *self {
+            Memory::Ewram => "EWRAM",
+            Memory::Iwram => "IWRAM",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Memory, String> {
+        match s {
+            "ewram" => Ok(Memory::Ewram),
+            "iwram" => Ok(Memory::Iwram),
+            _ => Err(format!("unknown memory `{}`, expected `ewram` or `iwram`", s)),
+        }
+    }
+}
+
+/// Memory set aside from the linker, e.g. for a buffer at a fixed
+/// address.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Reserved {
+    pub name: String,
+    pub memory: Memory,
+    pub size: u32,
+    pub address: Option<u32>,
+}
+
+/// An extra output section, loaded from the cartridge at startup.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Section {
+    /// The section name, including the leading `.`.
+    pub name: String,
+    pub memory: Memory,
+}
+
+impl Section {
+    /// The prefix of the section's `_start`, `_end` and `_lma` symbols.
+    pub fn symbol(&self) -> String {
+        format!("__{}", &self.name[1..])
+    }
+}
+
+/// A fixed range of memory, after the layout is resolved.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Region {
+    pub name: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// The top of IWRAM is used by the BIOS: its variables from
+/// 0x03007FE0 and the supervisor stack below them.
+pub const SP_IRQ: u32 = 0x0300_7FA0;
+
+/// Largest image the BIOS will load over the link cable.
+pub const MULTIBOOT_MAX_SIZE: u32 = 0x4_0000;
+
+const BUILTIN_SECTIONS: &[&str] = &[
+    ".header", ".multiboot", ".init", ".text", ".rodata", ".iwram", ".iwrambss",
+    ".ewram", ".data", ".bss",
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Layout {
+    /// Bytes of IRQ stack, growing down from `SP_IRQ`.
+    pub irq_stack: u32,
+
+    /// Minimum bytes of user stack, growing down from below the IRQ
+    /// stack.
+    pub usr_stack: u32,
+
+    /// Where the heap goes, taking whatever is left after sections.
+    pub heap: Memory,
+
+    pub reserved: Vec<Reserved>,
+
+    pub sections: Vec<Section>,
+
+    /// Link into EWRAM for the BIOS to load over the link cable.
+    pub multiboot: bool,
+}
+
+impl Default for Layout {
+    /// The BIOS defaults, with at least 1KiB of user stack.
+    fn default() -> Layout {
+        Layout {
+            irq_stack: 0xA0,
+            usr_stack: 0x400,
+            heap: Memory::Ewram,
+            reserved: Vec::new(),
+            sections: Vec::new(),
+            multiboot: false,
+        }
+    }
+}
+
+fn parse_number(s: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid number `{}`", s);
+    let mut parts = s.splitn(2, 'K');
+    let (digits, scale) = match (parts.next().unwrap(), parts.next()) {
+        (digits, None) => (digits, 1),
+        (digits, Some("")) => (digits, 1024),
+        _ => return Err(invalid()),
+    };
+    let mut parts = digits.splitn(2, "0x");
+    let value = match (parts.next().unwrap(), parts.next()) {
+        (decimal, None) => decimal.parse(),
+        ("", Some(hex)) => u32::from_str_radix(hex, 16),
+        _ => return Err(invalid()),
+    };
+    value.ok()
+        .and_then(|value| value.checked_mul(scale))
+        .ok_or_else(invalid)
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_digit(36) || c == '_')
+}
+
+impl Layout {
+    /// Reads the layout named by `GBA_RT_LAYOUT`, or the default
+    /// layout if it isn't set, in multiboot mode if the `multiboot`
+    /// feature is enabled.
+    pub fn from_env() -> Layout {
+        let mut layout = match ::std::env::var("GBA_RT_LAYOUT") {
+            Ok(path) => {
+                let mut config = String::new();
+                File::open(&path)
+                    .and_then(|mut f| f.read_to_string(&mut config))
+                    .unwrap_or_else(|e| panic!("Couldn't read layout {}: {}", path, e));
+                Layout::parse(&config).unwrap_or_else(|e| panic!("{}: {}", path, e))
+            }
+            Err(_) => Layout::default(),
+        };
+        layout.multiboot = ::std::env::var("CARGO_FEATURE_MULTIBOOT").is_ok();
+        layout
+    }
+
+    /// The memory the image is loaded from, as named in the linker
+    /// script.
+    pub fn rom(&self) -> &'static str {
+        if self.multiboot { Memory::Ewram.region() } else { "CART" }
+    }
+
+    pub fn parse(config: &str) -> Result<Layout, String> {
+        let mut layout = Layout::default();
+        for (number, line) in config.lines().enumerate() {
+            layout.parse_line(line.split('#').next().unwrap())
+                .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        }
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().unwrap().trim();
+        let value = parts.next().ok_or_else(|| "expected `key = value`".to_string())?.trim();
+        let words: Vec<&str> = value.split_whitespace().collect();
+        match (key, words.len()) {
+            ("irq_stack", 1) => self.irq_stack = parse_number(words[0])?,
+            ("usr_stack", 1) => self.usr_stack = parse_number(words[0])?,
+            ("heap", 1) => self.heap = Memory::parse(words[0])?,
+            ("reserve", 3) | ("reserve", 4) => self.reserved.push(Reserved {
+                name: words[0].to_string(),
+                memory: Memory::parse(words[1])?,
+                size: parse_number(words[2])?,
+                address: match words.get(3) {
+                    Some(address) => Some(parse_number(address)?),
+                    None => None,
+                },
+            }),
+            ("section", 2) => self.sections.push(Section {
+                name: words[0].to_string(),
+                memory: Memory::parse(words[1])?,
+            }),
+            ("irq_stack", _) | ("usr_stack", _) | ("heap", _) | ("reserve", _) |
+            ("section", _) => return Err(format!("wrong number of values for `{}`", key)),
+            _ => return Err(format!("unknown key `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// The user stack pointer at startup.
+    pub fn sp_usr(&self) -> u32 {
+        SP_IRQ - self.irq_stack
+    }
+
+    /// Every fixed region of work RAM: the BIOS area, the stacks and
+    /// reserved regions.
+    pub fn regions(&self) -> Vec<Region> {
+        let mut regions = vec![
+            Region { name: "BIOS".to_string(), start: SP_IRQ, end: 0x0300_8000 },
+            Region { name: "IRQ stack".to_string(), start: self.sp_usr(), end: SP_IRQ },
+            Region {
+                name: "user stack".to_string(),
+                start: self.sp_usr().wrapping_sub(self.usr_stack),
+                end: self.sp_usr(),
+            },
+        ];
+        let mut tops = [Memory::Ewram.origin() + Memory::Ewram.length(),
+                        self.sp_usr().wrapping_sub(self.usr_stack)];
+        for reserved in &self.reserved {
+            let start = match reserved.address {
+                Some(address) => address,
+                None => {
+                    let top = &mut tops[(reserved.memory == Memory::Iwram) as usize];
+                    *top = top.wrapping_sub(reserved.size);
+                    *top
+                }
+            };
+            regions.push(Region {
+                name: reserved.name.clone(),
+                start,
+                end: start.wrapping_add(reserved.size),
+            });
+        }
+        regions
+    }
+
+    /// Where sections and the heap in `memory` must end, below every
+    /// fixed region.
+    pub fn limit(&self, memory: Memory) -> u32 {
+        let end = memory.origin() + memory.length();
+        self.regions()
+            .iter()
+            .filter(|region| region.start >= memory.origin() && region.start < end)
+            .map(|region| region.start)
+            .min()
+            .unwrap_or(end)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.irq_stack & 3 != 0 || self.usr_stack & 3 != 0 {
+            return Err("stack sizes must be multiples of 4".to_string());
+        }
+        match self.irq_stack.checked_add(self.usr_stack) {
+            Some(stacks) if stacks <= SP_IRQ - Memory::Iwram.origin() => {}
+            _ => return Err("stacks are larger than IWRAM".to_string()),
+        }
+
+        let mut names: Vec<&str> = Vec::new();
+        for reserved in &self.reserved {
+            if !is_identifier(&reserved.name) {
+                return Err(format!("invalid reserved region name `{}`", reserved.name));
+            }
+            names.push(&reserved.name);
+        }
+        for section in &self.sections {
+            if !section.name.starts_with('.') || !is_identifier(&section.name[1..]) {
+                return Err(format!("invalid section name `{}`", section.name));
+            }
+            if BUILTIN_SECTIONS.contains(&&section.name[..]) {
+                return Err(format!("section `{}` is already defined", section.name));
+            }
+            names.push(&section.name[1..]);
+        }
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(format!("`{}` is defined twice", name));
+            }
+        }
+
+        let regions = self.regions();
+        for (i, region) in regions.iter().enumerate() {
+            let inside = [Memory::Ewram, Memory::Iwram].iter().any(|memory| {
+                let origin = memory.origin();
+                region.start >= origin && region.start <= region.end &&
+                    region.end <= origin + memory.length()
+            });
+            if !inside {
+                return Err(format!("{} ({:#x}-{:#x}) is outside work RAM",
+                                   region.name, region.start, region.end));
+            }
+            for other in &regions[..i] {
+                if region.start < other.end && other.start < region.end {
+                    return Err(format!("{} ({:#x}-{:#x}) overlaps {} ({:#x}-{:#x})",
+                                       region.name, region.start, region.end,
+                                       other.name, other.start, other.end));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn extra_sections(&self, memory: Memory) -> String {
+        let mut out = String::new();
+        for section in self.sections.iter().filter(|section| section.memory == memory) {
+            let symbol = section.symbol();
+            write!(out, "
+    {name} ALIGN(4) :
+    {{
+        {symbol}_start = ABSOLUTE(.);
+        KEEP(*({name}))
+        KEEP(*({name}.*))
+        . = ALIGN(4);
+        {symbol}_end = .;
+    }} > {region} AT > {rom}
+    {symbol}_lma = LOADADDR({name});
+", name = section.name, symbol = symbol, region = memory.region(), rom = self.rom()).unwrap();
+        }
+        out
+    }
+
+    /// The end of the loaded image, in the linker script: the end of
+    /// whichever loaded section's load address is highest.
+    fn image_end(&self) -> String {
+        let mut names: Vec<&str> = vec![".header"];
+        if self.multiboot {
+            names.push(".multiboot");
+        }
+        names.extend(&[".init", ".text", ".rodata", ".iwram"]);
+        let extra = |memory| {
+            self.sections.iter().filter(move |section| section.memory == memory)
+                .map(|section| &section.name[..])
+        };
+        names.extend(extra(Memory::Iwram));
+        names.extend(&[".ewram", ".data"]);
+        names.extend(extra(Memory::Ewram));
+        names.iter()
+            .map(|name| format!("LOADADDR({0}) + SIZEOF({0})", name))
+            .fold(None, |end: Option<String>, section_end| match end {
+                Some(end) => Some(format!("MAX({}, {})", end, section_end)),
+                None => Some(section_end),
+            })
+            .unwrap()
+    }
+
+    /// Generates the linker script.
+    pub fn linker_script(&self) -> String {
+        let mut reserved = String::new();
+        for (region, _) in self.regions().iter().skip(3).zip(&self.reserved) {
+            writeln!(reserved, "__{name}_start = {start:#010x};\n__{name}_end = {end:#010x};",
+                     name = region.name, start = region.start, end = region.end).unwrap();
+        }
+        let (heap_start, heap_end) = match self.heap {
+            Memory::Ewram => ("__bss_end", "ORIGIN(EWRAM) + LENGTH(EWRAM)"),
+            Memory::Iwram => ("__iwrambss_end", "ORIGIN(IWRAM) + LENGTH(IWRAM)"),
+        };
+
+        // The BIOS jumps to 0xC0 in a multiboot image, and overwrites
+        // the boot mode and slave ID after it, so `.multiboot` branches
+        // over them to `_init`.
+        let (externs, multiboot, init_offset, image_check) = if self.multiboot {
+            ("_header _multiboot _init",
+             "
+    .multiboot ORIGIN(EWRAM) + 0xC0 :
+    {
+        KEEP(*(.multiboot))
+    } > EWRAM
+".to_string(),
+             "0xE4",
+             format!("ASSERT(({} - ORIGIN(EWRAM) <= {:#x}), \
+                      \"Multiboot image is larger than 256K\")\n",
+                     self.image_end(), MULTIBOOT_MAX_SIZE))
+        } else {
+            ("_header _init", String::new(), "0xC0", String::new())
+        };
+
+        let substitutions = [
+            ("externs", externs.to_string()),
+            ("rom", self.rom().to_string()),
+            ("multiboot", multiboot),
+            ("init_offset", init_offset.to_string()),
+            ("ewram_address", if self.multiboot { "" } else { "ORIGIN(EWRAM) " }.to_string()),
+            ("image_check", image_check),
+            ("ewram_length", format!("{:#x}", self.limit(Memory::Ewram) - Memory::Ewram.origin())),
+            ("iwram_length", format!("{:#x}", self.limit(Memory::Iwram) - Memory::Iwram.origin())),
+            ("sp_usr", format!("{:#010x}", self.sp_usr())),
+            ("sp_irq", format!("{:#010x}", SP_IRQ)),
+            ("reserved", reserved),
+            ("iwram_sections", self.extra_sections(Memory::Iwram)),
+            ("ewram_sections", self.extra_sections(Memory::Ewram)),
+            ("heap_start", heap_start.to_string()),
+            ("heap_end", heap_end.to_string()),
+        ];
+        substitutions.iter().fold(include_str!("gba.ld").to_string(), |script, &(key, ref value)| {
+            script.replace(&format!("@{}@", key), value)
+        })
+    }
+
+    /// Generates `load_extra_sections`, which startup calls to copy
+    /// extra sections from the cartridge, or EWRAM in multiboot mode.
+    pub fn loader(&self) -> String {
+        let mut externs = String::new();
+        let mut loads = String::new();
+        for section in &self.sections {
+            let symbol = section.symbol();
+            write!(externs, "    static mut {0}_start: u8;\n    static {0}_end: u8;\n    \
+                             static {0}_lma: u8;\n", symbol).unwrap();
+            writeln!(loads, "    load_section(&mut {0}_start, &{0}_end, &{0}_lma);",
+                     symbol).unwrap();
+        }
+        format!("extern \"C\" {{\n{}}}\n\nunsafe fn load_extra_sections() {{\n{}}}\n",
+                externs, loads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(config: &str) -> String {
+        Layout::parse(config).unwrap_err()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(Ok(0x630), parse_number("0x630"));
+        assert_eq!(Ok(1000), parse_number("1000"));
+        assert_eq!(Ok(2048), parse_number("2K"));
+        assert_eq!(Ok(0x800), parse_number("0x2K"));
+        for s in &["", "K", "0x", "-1", "1k", "1KK", "0x0x1", "0xG", "4294967296", "4194304K"] {
+            assert_eq!(Err(format!("invalid number `{}`", s)), parse_number(s));
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("line 2: expected `key = value`", error("heap = iwram\nheap iwram"));
+        assert_eq!("line 1: unknown key `stack`", error("stack = 1K"));
+        assert_eq!("line 1: wrong number of values for `reserve`", error("reserve = a iwram"));
+        assert_eq!("line 1: unknown memory `vram`, expected `ewram` or `iwram`",
+                   error("section = .a vram"));
+        assert_eq!("line 1: invalid number `lots`", error("usr_stack = lots # too many"));
+        assert_eq!("invalid reserved region name `a-b`", error("reserve = a-b iwram 4"));
+        assert_eq!("invalid section name `a`", error("section = a ewram"));
+        assert_eq!("section `.data` is already defined", error("section = .data ewram"));
+        assert_eq!("`a` is defined twice", error("reserve = a iwram 4\nsection = .a ewram"));
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_ignored() {
+        let layout = Layout::parse("# a layout\n\n  irq_stack = 0x100 # more\n").unwrap();
+        assert_eq!(Layout { irq_stack: 0x100, ..Layout::default() }, layout);
+    }
+
+    #[test]
+    fn stacks_must_fit_in_iwram() {
+        assert_eq!("stack sizes must be multiples of 4", error("usr_stack = 6"));
+        assert_eq!("stacks are larger than IWRAM", error("usr_stack = 32K"));
+        // big enough that adding them up overflows
+        assert_eq!("stacks are larger than IWRAM",
+                   error("irq_stack = 0xFFFFFFFC\nusr_stack = 0x400"));
+        assert_eq!("stacks are larger than IWRAM",
+                   error("irq_stack = 0x400\nusr_stack = 0xFFFFFFFC"));
+
+        let all = SP_IRQ - Memory::Iwram.origin();
+        let layout = Layout::parse(&format!("usr_stack = {}", all - 0xA0)).unwrap();
+        assert_eq!(Memory::Iwram.origin(), layout.limit(Memory::Iwram));
+        assert!(Layout::parse(&format!("usr_stack = {}", all - 0x9C)).is_err());
+    }
+
+    #[test]
+    fn reserved_regions_are_stacked_from_the_top() {
+        let layout = Layout::parse("reserve = a iwram 0x100\nreserve = b iwram 0x20\n\
+                                    reserve = c ewram 1K").unwrap();
+        let regions = layout.regions();
+        let sp_usr_bottom = SP_IRQ - 0xA0 - 0x400;
+        assert_eq!(Region { name: "a".to_string(), start: sp_usr_bottom - 0x100,
+                            end: sp_usr_bottom },
+                   regions[3]);
+        assert_eq!(Region { name: "b".to_string(), start: sp_usr_bottom - 0x120,
+                            end: sp_usr_bottom - 0x100 },
+                   regions[4]);
+        assert_eq!(Region { name: "c".to_string(), start: 0x0203_FC00, end: 0x0204_0000 },
+                   regions[5]);
+        assert_eq!(sp_usr_bottom - 0x120, layout.limit(Memory::Iwram));
+        assert_eq!(0x0203_FC00, layout.limit(Memory::Ewram));
+    }
+
+    #[test]
+    fn overlapping_regions_are_rejected() {
+        assert_eq!("b (0x2000080-0x2000180) overlaps a (0x2000000-0x2000100)",
+                   error("reserve = a ewram 0x100 0x02000000\n\
+                          reserve = b ewram 0x100 0x02000080"));
+        // the user stack
+        assert_eq!("a (0x3007000-0x3007f00) overlaps user stack (0x3007b00-0x3007f00)",
+                   error("reserve = a iwram 0xF00 0x03007000"));
+        // adjacent regions are fine
+        assert!(Layout::parse("reserve = a ewram 0x100 0x02000000\n\
+                               reserve = b ewram 0x100 0x02000100").is_ok());
+    }
+
+    #[test]
+    fn multiboot_images_are_checked_up_to_the_last_load_address() {
+        let mut layout = Layout::parse("section = .a iwram\nsection = .b ewram").unwrap();
+        assert!(!layout.linker_script().contains("ASSERT((MAX("));
+
+        layout.multiboot = true;
+        let script = layout.linker_script();
+        let check = script.lines().find(|line| line.starts_with("ASSERT((MAX(")).unwrap();
+        for name in &[".header", ".multiboot", ".init", ".text", ".rodata", ".iwram", ".a",
+                      ".ewram", ".data", ".b"] {
+            assert!(check.contains(&format!("LOADADDR({0}) + SIZEOF({0})", name)), "{}", name);
+        }
+        assert!(check.ends_with("- ORIGIN(EWRAM) <= 0x40000), \
+                                 \"Multiboot image is larger than 256K\")"));
+        assert!(!check.contains("bss"));
+    }
+
+    #[test]
+    fn regions_outside_work_ram_are_rejected() {
+        assert_eq!("a (0x6000000-0x6000100) is outside work RAM",
+                   error("reserve = a iwram 0x100 0x06000000"));
+        assert_eq!("a (0x203ff00-0x2040100) is outside work RAM",
+                   error("reserve = a ewram 0x200 0x0203FF00"));
+        // wrapping around the top of memory
+        assert_eq!("a (0xffffff00-0x100) is outside work RAM",
+                   error("reserve = a ewram 0x200 0xFFFFFF00"));
+        // more than is left below the stacks
+        assert!(error("reserve = a iwram 0x8000").ends_with("is outside work RAM"));
+    }
+}
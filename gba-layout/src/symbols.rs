@@ -0,0 +1,83 @@
+//! Checks that startup does something with every section the linker
+//! script lays out, so e.g. a new NOLOAD section isn't left uncleared.
+
+// Symbols the linker script sets to a section address, like
+// `__bss_start = ABSOLUTE(.);` or `__data_lma = LOADADDR(.data);`.
+fn section_symbols(ld: &str) -> Vec<&str> {
+    ld.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap().trim();
+            let value = parts.next().map(|value| value.trim()).unwrap_or("");
+            if name.starts_with("__") &&
+                (value.starts_with("ABSOLUTE(.)") || value.starts_with(".;") ||
+                 value.starts_with("LOADADDR(")) {
+                Some(name)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// The identifiers in a line of code, split at anything that can't be
+// part of one.
+fn identifiers(code: &str) -> Vec<&str> {
+    code.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// The names declared in Rust source's `extern "C"` blocks, and the
+// identifiers used outside them, ignoring comments.
+fn rust_symbols(source: &str) -> (Vec<&str>, Vec<&str>) {
+    let mut declared = Vec::new();
+    let mut used = Vec::new();
+    let mut in_extern = false;
+    for line in source.lines() {
+        let code = line.split("//").next().unwrap();
+        let words = identifiers(code);
+        if in_extern {
+            // `static name: T;`, `static mut name: T;` or `fn name(...);`
+            for pair in words.windows(2) {
+                if ["static", "mut", "fn"].contains(&pair[0]) && pair[1] != "mut" {
+                    declared.push(pair[1]);
+                }
+            }
+            in_extern = !code.contains('}');
+        } else if words.starts_with(&["extern", "C"]) {
+            in_extern = true;
+        } else {
+            used.extend(words);
+        }
+    }
+    (declared, used)
+}
+
+/// The symbols the linker script `ld` sets to a section address that
+/// startup doesn't use.
+///
+/// A symbol counts as used when one of the Rust `sources`, e.g.
+/// `gba-rt`'s `src/lib.rs` and the generated loader, declares it in an
+/// `extern` block and refers to it outside the block, or when the
+/// `assembly` refers to it outside a comment.
+pub fn unused_section_symbols<'a>(ld: &'a str, sources: &[&str], assembly: &str)
+                                  -> Vec<&'a str> {
+    let mut declared = Vec::new();
+    let mut used = Vec::new();
+    for source in sources {
+        let (source_declared, source_used) = rust_symbols(source);
+        declared.extend(source_declared);
+        used.extend(source_used);
+    }
+    let assembly: Vec<&str> = assembly.lines()
+        .flat_map(|line| identifiers(line.split('@').next().unwrap()))
+        .collect();
+
+    section_symbols(ld)
+        .into_iter()
+        .filter(|name| {
+            !((declared.contains(name) && used.contains(name)) || assembly.contains(name))
+        })
+        .collect()
+}
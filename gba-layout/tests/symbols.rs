@@ -0,0 +1,56 @@
+//! Startup against the linker scripts generated for a few layouts.
+
+extern crate gba_layout;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use gba_layout::{unused_section_symbols, Layout};
+
+fn read(path: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../gba-rt").join(path);
+    let mut source = String::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_string(&mut source))
+        .unwrap_or_else(|e| panic!("Couldn't read {}: {}", path.display(), e));
+    source
+}
+
+fn unused(layout: &Layout) -> Vec<String> {
+    let (lib, init) = (read("src/lib.rs"), read("src/init.s"));
+    let ld = layout.linker_script();
+    let unused = unused_section_symbols(&ld, &[&lib, &layout.loader()], &init);
+    unused.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn startup_uses_every_section() {
+    let layouts = [
+        "",
+        "heap = iwram",
+        "section = .a iwram\nsection = .b ewram\nreserve = c iwram 0x100",
+    ];
+    for config in &layouts {
+        let mut layout = Layout::parse(config).unwrap();
+        for &multiboot in &[false, true] {
+            layout.multiboot = multiboot;
+            assert_eq!(Vec::<String>::new(), unused(&layout), "{:?}", layout);
+        }
+    }
+}
+
+#[test]
+fn unused_symbols_are_found() {
+    let ld = "__a_start = ABSOLUTE(.);\n__a_end = .;\n__a_lma = LOADADDR(.a);\n\
+              __b_start = ABSOLUTE(.);\n__c_start = 0x03000000;\n";
+    let rust = "extern \"C\" {\n    static mut __a_start: u8;\n    static __a_end: u8;\n\
+                static __b_start: u8;\n}\n\
+                // __b_start\n\
+                fn f() { load(&mut __a_start, &__a_end); }\n";
+    // declared but only mentioned in a comment, and not declared at all
+    assert_eq!(vec!["__a_lma", "__b_start"], unused_section_symbols(ld, &[rust], ""));
+    // assembly outside comments counts
+    assert_eq!(vec!["__b_start"],
+               unused_section_symbols(ld, &[rust], "ldr r0, =__a_lma @ __b_start"));
+}
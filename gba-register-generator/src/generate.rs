@@ -404,7 +404,25 @@ fn generate_register_read(register: &Register) -> RegisterMode {
     }
 }
 
+/// Generates a single register, along with the imports it needs.
 pub fn generate_register(register: &Register) -> Tokens {
+    generate_registers(&[register.clone()])
+}
+
+/// Generates several registers that share a module.
+pub fn generate_registers(registers: &[Register]) -> Tokens {
+    let register_tokens = registers.iter()
+        .map(generate_register_body)
+        .collect::<Vec<_>>();
+    quote! {
+        use core::mem;
+        use vcell::VolatileCell;
+
+        #(#register_tokens)*
+    }
+}
+
+fn generate_register_body(register: &Register) -> Tokens {
     assert!(!register.fields.is_empty(), "Must have at least one field");
 
     // first, generate all enums.
@@ -434,9 +452,6 @@ pub fn generate_register(register: &Register) -> Tokens {
     } = generate_register_write(register);
 
     quote! {
-        use core::mem;
-        use vcell::VolatileCell;
-
         #(#enum_definitions)*
 
         #register_struct
@@ -10,4 +10,4 @@ mod register;
 mod generate;
 
 pub use register::*;
-pub use generate::generate_register;
+pub use generate::{generate_register, generate_registers};
@@ -0,0 +1,77 @@
+// build.rs
+
+use std::process::Command;
+use std::env;
+use std::path::Path;
+use std::io::{Read, Write};
+use std::fs::File;
+
+extern crate gba_layout;
+use gba_layout::Layout;
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    // note that there are a number of downsides to this approach, the comments
+    // below detail how to improve the portability of these commands.
+    // The default header is a separate archive member, so it's only
+    // linked when the game doesn't define `_header` itself.  The
+    // multiboot header is only linked in multiboot mode.
+    for name in &["init", "header", "multiboot"] {
+        if !Command::new("armv4t-none-eabi-as").arg(&format!("src/{}.s", name)).arg("-o")
+                           .arg(&format!("{}/{}.o", out_dir, name))
+            .status().unwrap().success() {
+            panic!("Failed assembling {}", name);
+        }
+    }
+
+    if !Command::new("armv4t-none-eabi-ar")
+        .args(&["crus", "libgbainit.a", "init.o", "header.o", "multiboot.o"])
+        .current_dir(&Path::new(&out_dir))
+        .status().unwrap().success() {
+        panic!("Failed linking gbainit");
+    }
+    println!("cargo:rustc-link-search=native={}", out_dir);
+    println!("cargo:rustc-link-lib=static=gbainit");
+
+    // Create a linker script needed by the game boy advance platform,
+    // along with code to load any extra sections it has.
+    let layout = Layout::from_env();
+    let ld = layout.linker_script();
+    let loader = layout.loader();
+    File::create(Path::new(&out_dir).join("sections.rs"))
+        .unwrap()
+        .write_all(loader.as_bytes())
+        .unwrap();
+
+    check_section_symbols(&ld, &loader);
+
+    File::create(Path::new(&out_dir).join("gba.ld"))
+        .unwrap()
+        .write_all(ld.as_bytes())
+        .unwrap();
+
+    println!("cargo:rustc-link-search={}", out_dir);
+
+    println!("cargo:rerun-if-env-changed=GBA_RT_LAYOUT");
+    if let Ok(path) = env::var("GBA_RT_LAYOUT") {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+    for path in &["build", "src"] {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+}
+
+// A backstop for the test in gba-layout, in case a game's own layout
+// lays out something startup misses.
+fn check_section_symbols(ld: &str, loader: &str) {
+    let mut lib = String::new();
+    File::open("src/lib.rs").unwrap().read_to_string(&mut lib).unwrap();
+    let mut init = String::new();
+    File::open("src/init.s").unwrap().read_to_string(&mut init).unwrap();
+
+    let unused = gba_layout::unused_section_symbols(ld, &[&lib, loader], &init);
+    if !unused.is_empty() {
+        panic!("Linker script symbols not used by startup: {}", unused.join(", "));
+    }
+}
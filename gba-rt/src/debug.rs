@@ -0,0 +1,204 @@
+//! Logging to an emulator's debug output.
+//!
+//! mGBA and no$gba both have registers a game can write messages to,
+//! which they show in their logs.  The first message finds out which
+//! one the game is running in; on hardware, or in other emulators,
+//! messages go nowhere.
+//!
+//! Messages are written with `log!`, or with `error!`, `warn!`, `info!`
+//! and `debug!` for each level, which format their arguments like
+//! `format!`.  Panics are logged as errors.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[macro_use]
+//! extern crate gba_rt;
+//!
+//! #[no_mangle]
+//! pub fn main(_gba: gba_rt::Gba) -> ! {
+//!     info!("logging to {:?}", gba_rt::debug::backend());
+//!     let lives = 3;
+//!     debug!("{} lives left", lives);
+//!     loop {}
+//! }
+//! # fn main() {}
+//! ```
+
+use core::fmt::{self, Write};
+use core::ptr;
+use sync::critical_section;
+
+/// The buffer mGBA reads a message from.
+const MGBA_STRING: *mut u8 = 0x04FF_F600 as *mut u8;
+const MGBA_STRING_SIZE: usize = 0x100;
+/// Written with a level and `MGBA_SEND` to log the buffer.
+const MGBA_FLAGS: *mut u16 = 0x04FF_F700 as *mut u16;
+const MGBA_SEND: u16 = 0x100;
+/// Reads `MGBA_ENABLED` after `MGBA_ENABLE` is written to it.
+const MGBA_ENABLE_REG: *mut u16 = 0x04FF_F780 as *mut u16;
+const MGBA_ENABLE: u16 = 0xC0DE;
+const MGBA_ENABLED: u16 = 0x1DEA;
+
+/// Where no$gba's name and version can be read.
+const NOCASH_ID: *const u8 = 0x04FF_FA00 as *const u8;
+/// Logs each character written to it, ending the message at a newline.
+const NOCASH_CHAR_OUT: *mut u8 = 0x04FF_FA1C as *mut u8;
+
+/// Message levels, most severe first, numbered as mGBA numbers them.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    /// mGBA stops the game and shows fatal messages in a dialog.
+    Fatal,
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+/// Where messages go.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Backend {
+    /// mGBA's debug registers.
+    Mgba,
+    /// no$gba's message port.
+    NoCashGba,
+    /// Nowhere, e.g. on hardware.
+    None,
+}
+
+static mut BACKEND: Option<Backend> = None;
+
+fn detect() -> Backend {
+    unsafe {
+        ptr::write_volatile(MGBA_ENABLE_REG, MGBA_ENABLE);
+        if ptr::read_volatile(MGBA_ENABLE_REG) == MGBA_ENABLED {
+            return Backend::Mgba;
+        }
+        let mut id = [0u8; 6];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = ptr::read_volatile(NOCASH_ID.offset(i as isize));
+        }
+        if &id == b"no$gba" {
+            return Backend::NoCashGba;
+        }
+    }
+    Backend::None
+}
+
+/// Which emulator's debug output messages go to, found the first time
+/// it's needed.
+pub fn backend() -> Backend {
+    critical_section(|_| unsafe {
+        if BACKEND.is_none() {
+            BACKEND = Some(detect());
+        }
+        BACKEND.unwrap()
+    })
+}
+
+/// Writes one message to the backend.
+struct Message {
+    backend: Backend,
+    level: Level,
+    len: usize,
+}
+
+impl Message {
+    // Sends what's in mGBA's buffer as one message.
+    fn send(&mut self) {
+        unsafe { ptr::write_volatile(MGBA_FLAGS, self.level as u16 | MGBA_SEND) };
+        self.len = 0;
+    }
+
+    fn finish(&mut self) {
+        match self.backend {
+            Backend::Mgba => self.send(),
+            Backend::NoCashGba => unsafe { ptr::write_volatile(NOCASH_CHAR_OUT, b'\n') },
+            Backend::None => {}
+        }
+    }
+}
+
+impl Write for Message {
+    /// mGBA messages too long for its buffer are split across several.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.backend {
+            Backend::Mgba => {
+                // mGBA clears the buffer after each message, so it's
+                // always terminated
+                for &byte in s.as_bytes() {
+                    if self.len == MGBA_STRING_SIZE {
+                        self.send();
+                    }
+                    unsafe { ptr::write_volatile(MGBA_STRING.offset(self.len as isize), byte) };
+                    self.len += 1;
+                }
+            }
+            Backend::NoCashGba => {
+                for &byte in s.as_bytes() {
+                    unsafe { ptr::write_volatile(NOCASH_CHAR_OUT, byte) };
+                }
+            }
+            Backend::None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Logs a message at `level`.  Use `log!` and friends instead of
+/// calling this directly.
+///
+/// Interrupts are masked while the message is written, so messages
+/// logged by interrupt handlers don't get mixed into others.
+pub fn log(level: Level, args: fmt::Arguments) {
+    let backend = backend();
+    if backend == Backend::None {
+        return;
+    }
+    critical_section(|_| {
+        let mut message = Message { backend, level, len: 0 };
+        let _ = message.write_fmt(args);
+        message.finish();
+    });
+}
+
+/// Logs a message at a `debug::Level`, formatted like `format!`.
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::debug::log($level, format_args!($($arg)+))
+    }
+}
+
+/// Logs an error, formatted like `format!`.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::debug::log($crate::debug::Level::Error, format_args!($($arg)+))
+    }
+}
+
+/// Logs a warning, formatted like `format!`.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::debug::log($crate::debug::Level::Warn, format_args!($($arg)+))
+    }
+}
+
+/// Logs information, formatted like `format!`.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::debug::log($crate::debug::Level::Info, format_args!($($arg)+))
+    }
+}
+
+/// Logs debugging detail, formatted like `format!`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::debug::log($crate::debug::Level::Debug, format_args!($($arg)+))
+    }
+}
@@ -0,0 +1,535 @@
+//! Ownership-based access to the hardware.
+//!
+//! `main` receives a `Gba`, which owns a handle to each peripheral.
+//! Handles can't be copied or conjured up, so as long as all hardware
+//! access goes through them, no two parts of the program can
+//! configure the same peripheral behind each other's backs.
+//!
+//! Interrupt handlers get a `CriticalGba` instead, which can only
+//! observe hardware state.  To let a handler drive a peripheral, move
+//! its handle into a `sync::Mutex`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate gba_hw;
+//! extern crate gba_rt;
+//!
+//! use gba_hw::video::{DisplayControlWrite, BG2};
+//!
+//! #[no_mangle]
+//! pub fn main(mut gba: gba_rt::Gba) -> ! {
+//!     gba.video.display_control().write(
+//!         DisplayControlWrite::default()
+//!             .set_video_mode(3)
+//!             .set_display_layers(BG2));
+//!     for pixel in gba.video.vram().iter_mut() {
+//!         *pixel = 31;
+//!     }
+//!     loop {}
+//! }
+//! # fn main() {}
+//! ```
+
+use core::slice;
+use gba_bios::{self, MultiBootMode, Transport};
+use gba_hw::{ReadOnly, ReadWrite, WriteOnly};
+use gba_hw::{dma, interrupts, keypad, sio, sound, timers, video};
+use gba_hw::interrupts::{MasterEnable, SourceSet};
+use gba_hw::keypad::KeySet;
+use gba_hw::sio::{BaudRate, SioControl, SioControlWrite, SioMode};
+use sync::critical_section;
+
+// Kept in .data, which is loaded before anything else runs, so `take`
+// doesn't depend on .bss having been zeroed.
+#[link_section = ".data"]
+static mut TAKEN: bool = false;
+
+/// Owns every peripheral.
+pub struct Gba {
+    /// Display controller and VRAM.
+    pub video: Video,
+
+    /// DMA channels 0-3.
+    pub dma: (DmaChannel, DmaChannel, DmaChannel, DmaChannel),
+
+    /// Timers 0-3.
+    pub timers: (Timer, Timer, Timer, Timer),
+
+    /// Sound controller.
+    pub sound: Sound,
+
+    /// Keypad.
+    pub keypad: Keypad,
+
+    /// Serial port.
+    pub sio: Sio,
+
+    /// The cartridge's save memory, for one of the `save` drivers.
+    pub save: Save,
+
+    /// The cartridge's GPIO port, for `gpio::CartGpio`.
+    pub gpio: Gpio,
+
+    /// Interrupt controller.
+    pub interrupts: Interrupts,
+}
+
+impl Gba {
+    /// Returns the peripherals the first time it is called, and
+    /// `None` afterwards.
+    ///
+    /// The runtime calls this before `main`, so normally `main`'s
+    /// argument is the only `Gba`.
+    pub fn take() -> Option<Gba> {
+        let taken = critical_section(|_| unsafe {
+            let taken = TAKEN;
+            TAKEN = true;
+            taken
+        });
+        if taken {
+            None
+        } else {
+            Some(unsafe { Gba::steal() })
+        }
+    }
+
+    /// Creates the peripherals regardless of whether they have
+    /// already been taken.
+    ///
+    /// This is unsafe because it allows aliasing handles.
+    pub unsafe fn steal() -> Gba {
+        Gba {
+            video: Video { _private: () },
+            dma: (DmaChannel { regs: dma::channel0() },
+                  DmaChannel { regs: dma::channel1() },
+                  DmaChannel { regs: dma::channel2() },
+                  DmaChannel { regs: dma::channel3() }),
+            timers: (Timer { regs: timers::timer0() },
+                     Timer { regs: timers::timer1() },
+                     Timer { regs: timers::timer2() },
+                     Timer { regs: timers::timer3() }),
+            sound: Sound { _private: () },
+            keypad: Keypad { _private: () },
+            sio: Sio { _private: () },
+            save: Save { _private: () },
+            gpio: Gpio { _private: () },
+            interrupts: Interrupts { _private: () },
+        }
+    }
+}
+
+/// The display controller and VRAM.
+pub struct Video {
+    _private: (),
+}
+
+impl Video {
+    /// The `DISPCNT` register.
+    #[inline(always)]
+    pub fn display_control(&mut self) -> &mut video::DisplayControl {
+        unsafe { video::display_control() }
+    }
+
+    /// The `DISPSTAT` register.
+    #[inline(always)]
+    pub fn display_status(&mut self) -> &mut video::DisplayStatus {
+        unsafe { video::display_status() }
+    }
+
+    /// The line currently being drawn.
+    #[inline(always)]
+    pub fn vcount(&self) -> u16 {
+        unsafe { video::vcount().read() }
+    }
+
+    /// All 96KiB of VRAM, which can only be written 16 bits at a time.
+    #[inline(always)]
+    pub fn vram(&mut self) -> &mut [u16] {
+        unsafe { slice::from_raw_parts_mut(0x600_0000 as *mut u16, 0xC000) }
+    }
+
+    /// Object attribute memory, which can only be written 16 bits at
+    /// a time.
+    #[inline(always)]
+    pub fn oam(&mut self) -> &mut [u16] {
+        unsafe { slice::from_raw_parts_mut(0x700_0000 as *mut u16, 0x200) }
+    }
+
+    /// Rotation and scaling for background 2.
+    #[inline(always)]
+    pub fn bg2_affine(&mut self) -> &mut video::BackgroundAffine {
+        unsafe { video::bg2_affine() }
+    }
+
+    /// Rotation and scaling for background 3.
+    #[inline(always)]
+    pub fn bg3_affine(&mut self) -> &mut video::BackgroundAffine {
+        unsafe { video::bg3_affine() }
+    }
+}
+
+/// A single DMA channel.
+pub struct DmaChannel {
+    regs: &'static mut dma::Channel,
+}
+
+impl DmaChannel {
+    /// Whether this is channel 3, the only one that can reach the
+    /// cartridge's save memory.
+    #[inline(always)]
+    pub fn is_channel3(&self) -> bool {
+        &*self.regs as *const dma::Channel == unsafe { dma::channel3() as *const dma::Channel }
+    }
+
+    /// The channel's control register, e.g. to stop a repeating transfer.
+    #[inline(always)]
+    pub fn control(&mut self) -> &mut dma::DmaControl {
+        &mut self.regs.control
+    }
+
+    /// Starts a transfer with the given settings.
+    ///
+    /// This is unsafe because the DMA controller will write to
+    /// `dest` with no regard for Rust's aliasing rules, possibly long
+    /// after this function returns.
+    #[inline]
+    pub unsafe fn start(&mut self,
+                        source: *const u8,
+                        dest: *mut u8,
+                        count: u16,
+                        control: &dma::DmaControlWrite) {
+        self.regs.control.update(|mut w| { w.disable(); w });
+        self.regs.source.write(source as u32);
+        self.regs.dest.write(dest as u32);
+        self.regs.count.write(count);
+        self.regs.control.write(control);
+    }
+
+    /// Copies `source` to `dest` immediately, halting the CPU until
+    /// the copy finishes.
+    ///
+    /// `source` and `dest` must have the same length, which must fit
+    /// in the channel's `count` register.
+    pub fn copy_words(&mut self, source: &[u32], dest: &mut [u32]) {
+        assert_eq!(source.len(), dest.len());
+        assert!(source.len() <= 0x4000);
+        if source.is_empty() {
+            return;
+        }
+        unsafe {
+            self.start(source.as_ptr() as *const u8,
+                       dest.as_mut_ptr() as *mut u8,
+                       source.len() as u16,
+                       dma::DmaControlWrite::default()
+                           .set_unit_size(dma::UnitSize::Word)
+                           .set_start_timing(dma::StartTiming::Immediately)
+                           .enable());
+        }
+    }
+}
+
+/// A single timer.
+pub struct Timer {
+    regs: &'static mut timers::Timer,
+}
+
+impl Timer {
+    /// The current count.
+    #[inline(always)]
+    pub fn counter(&self) -> u16 {
+        self.regs.counter.read()
+    }
+
+    /// Sets the value loaded on overflow and when the timer starts.
+    #[inline(always)]
+    pub fn set_reload(&mut self, reload: u16) {
+        self.regs.counter.write(reload)
+    }
+
+    /// The timer's control register.
+    #[inline(always)]
+    pub fn control(&mut self) -> &mut timers::TimerControl {
+        &mut self.regs.control
+    }
+}
+
+/// The sound controller.
+pub struct Sound {
+    _private: (),
+}
+
+impl Sound {
+    /// The `SOUNDCNT_L` register.
+    #[inline(always)]
+    pub fn psg_control(&mut self) -> &mut ReadWrite<u16> {
+        unsafe { sound::psg_control() }
+    }
+
+    /// The `SOUNDCNT_H` register.
+    #[inline(always)]
+    pub fn mix_control(&mut self) -> &mut ReadWrite<u16> {
+        unsafe { sound::mix_control() }
+    }
+
+    /// The `SOUNDCNT_X` register.
+    #[inline(always)]
+    pub fn master_control(&mut self) -> &mut ReadWrite<u16> {
+        unsafe { sound::master_control() }
+    }
+
+    /// The `SOUNDBIAS` register.
+    #[inline(always)]
+    pub fn bias(&mut self) -> &mut ReadWrite<u16> {
+        unsafe { sound::bias() }
+    }
+
+    /// Direct sound FIFO A.
+    #[inline(always)]
+    pub fn fifo_a(&mut self) -> &mut WriteOnly<u32> {
+        unsafe { sound::fifo_a() }
+    }
+
+    /// Direct sound FIFO B.
+    #[inline(always)]
+    pub fn fifo_b(&mut self) -> &mut WriteOnly<u32> {
+        unsafe { sound::fifo_b() }
+    }
+}
+
+/// The keypad.
+pub struct Keypad {
+    _private: (),
+}
+
+impl Keypad {
+    /// Keys currently held down.
+    #[inline(always)]
+    pub fn pressed(&self) -> KeySet {
+        unsafe { keypad::pressed() }
+    }
+
+    /// The keypad interrupt control register.
+    #[inline(always)]
+    pub fn control(&mut self) -> &mut keypad::KeyControl {
+        unsafe { keypad::key_control() }
+    }
+}
+
+/// The serial port.
+pub struct Sio {
+    _private: (),
+}
+
+impl Sio {
+    /// The `SIOCNT` register.
+    #[inline(always)]
+    pub fn control(&mut self) -> &mut SioControl {
+        unsafe { sio::control() }
+    }
+
+    /// The `RCNT` register.
+    #[inline(always)]
+    pub fn mode_select(&mut self) -> &mut ReadWrite<u16> {
+        unsafe { sio::mode_select() }
+    }
+
+    /// Data register for 32-bit normal mode.
+    #[inline(always)]
+    pub fn data32(&mut self) -> &mut ReadWrite<u32> {
+        unsafe { sio::data32() }
+    }
+
+    /// Values received in multi-player mode, parent first.
+    #[inline(always)]
+    pub fn multi_data(&self) -> &[ReadOnly<u16>; 4] {
+        unsafe { sio::multi_data() }
+    }
+
+    /// Value to send in multi-player mode or 8-bit normal mode.
+    #[inline(always)]
+    pub fn send_data(&mut self) -> &mut ReadWrite<u16> {
+        unsafe { sio::send_data() }
+    }
+}
+
+/// Waits this many polls for a transfer to finish before giving up.
+const SIO_TIMEOUT: u32 = 0x1_0000;
+
+/// Scanlines in about 1/16 of a second.
+const DELAY_LINES: u32 = 851;
+
+/// The serial port set up as master for sending a multiboot image with
+/// `gba_bios::send`.
+pub struct SioTransport<'a> {
+    sio: &'a mut Sio,
+    mode: MultiBootMode,
+}
+
+impl Sio {
+    /// Sets up the serial port as master in `mode`, for sending a
+    /// multiboot image.
+    pub fn multiboot_transport(&mut self, mode: MultiBootMode) -> SioTransport {
+        let mut control = SioControlWrite::default();
+        match mode {
+            MultiBootMode::Multiplayer => {
+                control.set_mode(SioMode::MultiPlayer).set_baud_rate(BaudRate::Baud115200);
+            }
+            MultiBootMode::Normal256Khz => {
+                control.set_mode(SioMode::Normal32).set_internal_clock(true);
+            }
+            MultiBootMode::Normal2Mhz => {
+                control.set_mode(SioMode::Normal32).set_internal_clock(true).set_clock_2mhz(true);
+            }
+        }
+        self.mode_select().write(0);
+        self.control().write(&control);
+        SioTransport { sio: self, mode }
+    }
+}
+
+impl<'a> Transport for SioTransport<'a> {
+    /// In normal mode the value is sent in the low half of a 32-bit
+    /// transfer, and the client answers in the high half.
+    fn exchange(&mut self, data: u16) -> Option<[u16; 3]> {
+        let multiplayer = self.mode == MultiBootMode::Multiplayer;
+        if multiplayer {
+            self.sio.send_data().write(data);
+        } else {
+            self.sio.data32().write(data as u32);
+        }
+        self.sio.control().update(|mut w| { w.start(); w });
+
+        let mut polls = 0;
+        while self.sio.control().read().active() {
+            polls += 1;
+            if polls == SIO_TIMEOUT {
+                self.sio.control().update(|mut w| { w.stop(); w });
+                return None;
+            }
+        }
+
+        if !multiplayer {
+            return Some(gba_bios::normal_replies(self.sio.data32().read()));
+        }
+        if self.sio.control().read().multi_error() {
+            return None;
+        }
+        let data = self.sio.multi_data();
+        Some([data[1].read(), data[2].read(), data[3].read()])
+    }
+
+    fn delay(&mut self) {
+        let mut line = unsafe { video::vcount().read() };
+        let mut lines = 0;
+        while lines < DELAY_LINES {
+            let now = unsafe { video::vcount().read() };
+            if now != line {
+                line = now;
+                lines += 1;
+            }
+        }
+    }
+}
+
+/// The cartridge's save memory, before a driver has been picked.
+pub struct Save {
+    _private: (),
+}
+
+/// The cartridge's GPIO port, before it has been taken over.
+pub struct Gpio {
+    _private: (),
+}
+
+/// The interrupt controller.
+///
+/// Acknowledgement is left to the `interrupts` dispatcher.
+pub struct Interrupts {
+    _private: (),
+}
+
+impl Interrupts {
+    /// The `IE` register.
+    #[inline(always)]
+    pub fn enable(&mut self) -> &mut ReadWrite<SourceSet> {
+        unsafe { interrupts::enable() }
+    }
+
+    /// The `IME` register.
+    ///
+    /// Note that critical sections restore `IME` when they end.
+    ///
+    /// # Safety
+    ///
+    /// `IME` must not be turned on inside a critical section, e.g. with
+    /// this handle moved into a `sync::Mutex`, since an interrupt
+    /// handler could then run while the section borrows shared data.
+    #[inline(always)]
+    pub unsafe fn master_enable(&mut self) -> &mut ReadWrite<MasterEnable> {
+        interrupts::master_enable()
+    }
+}
+
+/// The view of the hardware available to interrupt handlers.
+///
+/// It can only observe state, so it can't interfere with handles
+/// owned by the main program.
+pub struct CriticalGba {
+    _private: (),
+}
+
+impl CriticalGba {
+    /// Only the dispatcher creates these.
+    pub(crate) unsafe fn new() -> CriticalGba {
+        CriticalGba { _private: () }
+    }
+
+    /// Sources pending in `IF`.
+    #[inline(always)]
+    pub fn sources(&self) -> SourceSet {
+        unsafe { interrupts::sources().read() }
+    }
+
+    /// Keys currently held down.
+    #[inline(always)]
+    pub fn pressed(&self) -> KeySet {
+        unsafe { keypad::pressed() }
+    }
+
+    /// The line currently being drawn.
+    #[inline(always)]
+    pub fn vcount(&self) -> u16 {
+        unsafe { video::vcount().read() }
+    }
+
+    /// The `DISPSTAT` register.
+    #[inline(always)]
+    pub fn display_status(&self) -> video::DisplayStatusRead {
+        unsafe { video::display_status().read() }
+    }
+
+    /// The current count of timer 0.
+    #[inline(always)]
+    pub fn timer0_counter(&self) -> u16 {
+        unsafe { timers::timer0().counter.read() }
+    }
+
+    /// The current count of timer 1.
+    #[inline(always)]
+    pub fn timer1_counter(&self) -> u16 {
+        unsafe { timers::timer1().counter.read() }
+    }
+
+    /// The current count of timer 2.
+    #[inline(always)]
+    pub fn timer2_counter(&self) -> u16 {
+        unsafe { timers::timer2().counter.read() }
+    }
+
+    /// The current count of timer 3.
+    #[inline(always)]
+    pub fn timer3_counter(&self) -> u16 {
+        unsafe { timers::timer3().counter.read() }
+    }
+}
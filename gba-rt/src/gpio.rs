@@ -0,0 +1,75 @@
+//! The cartridge's GPIO port, and drivers for the chips found on it.
+//!
+//! Some cartridges wire a real-time clock, rumble motor, solar sensor
+//! or gyro sensor to four pins mapped over the ROM at 0x080000C4.
+//! `CartGpio` drives those pins, and the drivers from `gba-gpio` talk
+//! to the chips over them.  Which chips are there is fixed by the
+//! cartridge, so the game picks the drivers.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! extern crate gba_rt;
+//!
+//! use gba_rt::gpio::{CartGpio, Rtc, SolarSensor};
+//!
+//! #[no_mangle]
+//! pub fn main(gba: gba_rt::Gba) -> ! {
+//!     let mut gpio = CartGpio::new(gba.gpio);
+//!
+//!     // the clock and solar sensor share the port
+//!     let time = Rtc::new(&mut gpio).time();
+//!     let light = SolarSensor::new(&mut gpio).read();
+//!     loop {}
+//! }
+//! # fn main() {}
+//! ```
+
+use core::ptr;
+use gba::Gpio;
+
+pub use gba_gpio::{Alarm, DateTime, Gyro, Pins, Rtc, RtcError, Rumble, SolarSensor, Status,
+                   Time};
+
+const DATA: *mut u16 = 0x0800_00C4 as *mut u16;
+const DIRECTION: *mut u16 = 0x0800_00C6 as *mut u16;
+const CONTROL: *mut u16 = 0x0800_00C8 as *mut u16;
+
+/// The pins read back as ROM unless this is set in `CONTROL`.
+const READ_ENABLE: u16 = 1;
+
+/// The four GPIO pins on the cartridge.
+pub struct CartGpio {
+    gpio: Gpio,
+}
+
+impl CartGpio {
+    /// Takes over the port, making the pins readable.
+    ///
+    /// While the port is taken over, the ROM bytes under it read as
+    /// the pins instead.
+    pub fn new(gpio: Gpio) -> CartGpio {
+        unsafe { ptr::write_volatile(CONTROL, READ_ENABLE) };
+        CartGpio { gpio }
+    }
+
+    /// Gives back the port, so the ROM under it reads normally again.
+    pub fn release(self) -> Gpio {
+        unsafe { ptr::write_volatile(CONTROL, 0) };
+        self.gpio
+    }
+}
+
+impl Pins for CartGpio {
+    fn set_outputs(&mut self, outputs: u8) {
+        unsafe { ptr::write_volatile(DIRECTION, outputs as u16 & 0xF) };
+    }
+
+    fn write(&mut self, value: u8) {
+        unsafe { ptr::write_volatile(DATA, value as u16 & 0xF) };
+    }
+
+    fn read(&mut self) -> u8 {
+        unsafe { ptr::read_volatile(DATA) as u8 & 0xF }
+    }
+}
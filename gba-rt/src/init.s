@@ -2,18 +2,88 @@
     .global _init
 _init:
     .arm
+    @ IRQ mode, with IRQs disabled
+    mov r0, #0x92
+    msr cpsr_c, r0
+    ldr sp, =__sp_irq
+    @ system mode
+    mov r0, #0x1F
+    msr cpsr_c, r0
     ldr sp, =__sp_usr
     blx (_start)
 
-    .section .header,#alloc
-    .global _header
-_header:
-    .arm
-    b (_init)
-
     .section .iwram
     .global __usr_irq_handler
 __usr_irq_handler:
     .arm
     ldr r12, =_irq_handler
     bx r12
+
+    @ Nested interrupt entry.  The BIOS has already saved r0-r3, r12
+    @ and lr on the IRQ stack; we additionally save spsr and lr_irq,
+    @ then run the dispatcher in system mode on the user stack so that
+    @ it may re-enable IRQs for higher priority sources.
+    .section .iwram
+    .global __usr_irq_handler_nested
+__usr_irq_handler_nested:
+    .arm
+    mrs r0, spsr
+    stmfd sp!, {r0, lr}
+
+    @ system mode, IRQs still disabled in CPSR
+    mrs r0, cpsr
+    orr r0, r0, #0x1F
+    msr cpsr_c, r0
+
+    stmfd sp!, {r0, lr}
+    ldr r12, =_irq_handler_nested
+    mov lr, pc
+    bx r12
+    ldmfd sp!, {r0, lr}
+
+    @ back to IRQ mode with IRQs disabled
+    mrs r0, cpsr
+    bic r0, r0, #0x1F
+    orr r0, r0, #0x92
+    msr cpsr_c, r0
+
+    ldmfd sp!, {r0, lr}
+    msr spsr_cf, r0
+    bx lr
+
+    @ u32 __cpsr_irq_disable()
+    @ Sets the CPSR I bit, returning its previous value (0 or 0x80).
+    .section .iwram
+    .global __cpsr_irq_disable
+    .type __cpsr_irq_disable, %function
+__cpsr_irq_disable:
+    .arm
+    mrs r0, cpsr
+    orr r1, r0, #0x80
+    msr cpsr_c, r1
+    and r0, r0, #0x80
+    bx lr
+
+    @ void __cpsr_irq_restore(u32 state)
+    @ Restores the CPSR I bit from a value returned by __cpsr_irq_disable.
+    .section .iwram
+    .global __cpsr_irq_restore
+    .type __cpsr_irq_restore, %function
+__cpsr_irq_restore:
+    .arm
+    mrs r1, cpsr
+    bic r1, r1, #0x80
+    orr r1, r1, r0
+    msr cpsr_c, r1
+    bx lr
+
+    @ u32 __frame_pointer()
+    @ Returns the caller's frame pointer, r7 in thumb code, for walking
+    @ the stack when showing a panic.
+    .section .text
+    .global __frame_pointer
+    .type __frame_pointer, %function
+__frame_pointer:
+    .arm
+    mov r0, r7
+    bx lr
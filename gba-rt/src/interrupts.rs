@@ -0,0 +1,210 @@
+//! Interrupt dispatch.
+//!
+//! Rather than overriding `handle_interrupts` and demultiplexing
+//! `interrupts::sources` by hand, register one handler per source:
+//!
+//! ```no_run
+//! extern crate gba_hw;
+//! extern crate gba_rt;
+//!
+//! use gba_hw::interrupts::{VBLANK, TIMER_0};
+//!
+//! use gba_rt::CriticalGba;
+//!
+//! fn on_vblank(_gba: &CriticalGba) { /* ... */ }
+//! fn on_timer(_gba: &CriticalGba) { /* ... */ }
+//!
+//! # fn main() {
+//! gba_rt::interrupts::set_handler(VBLANK, Some(on_vblank));
+//! gba_rt::interrupts::set_handler(TIMER_0, Some(on_timer));
+//!
+//! // Run the timer handler before the vblank handler when both are pending.
+//! gba_rt::interrupts::set_priority(&[TIMER_0, VBLANK]);
+//! # }
+//! ```
+//!
+//! Note that handlers are only called for sources that are also
+//! enabled in `interrupts::enable` and in their peripheral.
+//!
+//! By default, a handler runs with all other interrupts blocked until
+//! it returns.  Call `set_nesting(Nesting::Enabled)` to let higher
+//! priority sources interrupt lower priority handlers, e.g. so audio
+//! timers keep firing during a long vblank handler.
+
+use gba_hw::interrupts::{self, SourceSet};
+use gba::CriticalGba;
+use sync::critical_section;
+
+extern "C" {
+    fn __usr_irq_handler();
+    fn __usr_irq_handler_nested();
+    fn __cpsr_irq_disable() -> u32;
+    fn __cpsr_irq_restore(state: u32);
+}
+
+/// A function called when its interrupt source fires.
+///
+/// Handlers get a `CriticalGba`, a read-only view of the hardware.
+pub type Handler = fn(&CriticalGba);
+
+/// Number of distinct interrupt sources in a `SourceSet`.
+pub const SOURCE_COUNT: usize = 14;
+
+static mut HANDLERS: [Option<Handler>; SOURCE_COUNT] = [None; SOURCE_COUNT];
+
+// Source indices in the order their handlers are called.
+static mut PRIORITY: [u8; SOURCE_COUNT] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13];
+
+#[inline(always)]
+fn source_index(source: SourceSet) -> usize {
+    debug_assert!(source.bits().count_ones() == 1,
+                  "Expected exactly one interrupt source");
+    source.bits().trailing_zeros() as usize
+}
+
+#[inline(always)]
+fn index_source(index: u8) -> SourceSet {
+    SourceSet::from_bits_truncate(1 << index)
+}
+
+/// Whether handlers may be interrupted by higher priority sources.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Nesting {
+    /// Handlers run to completion with all interrupts blocked.
+    /// The IRQ path goes through the weak `handle_interrupts` hook.
+    Disabled,
+
+    /// Each handler runs in system mode with IRQs re-enabled, but
+    /// with `interrupts::enable` masked down to the sources that come
+    /// before it in the priority order.  The weak `handle_interrupts`
+    /// hook is bypassed.
+    ///
+    /// Handlers must not modify `interrupts::enable` in this mode, as
+    /// it is restored after each handler returns.
+    Enabled,
+}
+
+/// Registers `handler` for a single interrupt source, replacing any
+/// previous handler.  Passing `None` removes the handler.
+///
+/// # Arguments
+///
+/// * `source` exactly one interrupt source, e.g. `VBLANK`.
+/// * `handler` function to call when `source` fires.
+pub fn set_handler(source: SourceSet, handler: Option<Handler>) {
+    let index = source_index(source);
+    critical_section(|_| unsafe {
+        HANDLERS[index] = handler;
+    });
+}
+
+/// Sets the order in which handlers are called when several
+/// sources are pending at once.
+///
+/// Sources listed in `order` are called first, in the order given.
+/// Any sources not listed are called afterwards, in bit order
+/// (`VBLANK` first, `EXTERNAL` last).
+///
+/// # Arguments
+///
+/// * `order` single interrupt sources, highest priority first.
+pub fn set_priority(order: &[SourceSet]) {
+    let mut priority = [0u8; SOURCE_COUNT];
+    let mut listed = SourceSet::empty();
+    let mut count = 0;
+    for &source in order {
+        if listed.contains(source) {
+            continue;
+        }
+        listed.insert(source);
+        priority[count] = source_index(source) as u8;
+        count += 1;
+    }
+    for index in 0..SOURCE_COUNT as u8 {
+        if !listed.contains(index_source(index)) {
+            priority[count] = index;
+            count += 1;
+        }
+    }
+
+    critical_section(|_| unsafe {
+        PRIORITY = priority;
+    });
+}
+
+/// Selects the IRQ entry path used by the BIOS.
+pub fn set_nesting(nesting: Nesting) {
+    let entry = match nesting {
+        Nesting::Disabled => __usr_irq_handler as usize,
+        Nesting::Enabled => __usr_irq_handler_nested as usize,
+    };
+    critical_section(|_| unsafe {
+        let usr_irq_ptr = 0x03007FFC as *mut usize;
+        (*usr_irq_ptr) = entry;
+    });
+}
+
+/// Calls the registered handler for every pending, enabled source in
+/// priority order, then acknowledges exactly those sources in
+/// `irq_acknowledge` and adds them to `irq_acknowledge_bios`.
+///
+/// Sources without a handler are left pending, so every source
+/// enabled in `interrupts::enable` should have a handler registered.
+///
+/// This is what the default `handle_interrupts` does; call it from
+/// your own `handle_interrupts` if you override it.
+#[link_section = ".iwram"]
+pub fn dispatch() {
+    unsafe {
+        let pending = interrupts::sources().read() & interrupts::enable().read();
+        let mut handled = SourceSet::empty();
+        let gba = CriticalGba::new();
+        for &index in PRIORITY.iter() {
+            let source = index_source(index);
+            if !pending.contains(source) {
+                continue;
+            }
+            if let Some(handler) = HANDLERS[index as usize] {
+                handler(&gba);
+                handled.insert(source);
+            }
+        }
+        interrupts::irq_acknowledge().write(handled);
+        // a user handler or the BIOS may already have set flags
+        let bios = interrupts::irq_acknowledge_bios();
+        let flags = bios.read();
+        bios.write(flags | handled);
+    }
+}
+
+/// Like `dispatch`, but each source is acknowledged before its handler
+/// runs, and the handler runs with CPSR IRQs enabled and
+/// `interrupts::enable` restricted to higher priority sources.
+///
+/// Must only be called from the nested IRQ trampoline, which enters
+/// in system mode with CPSR IRQs disabled.
+#[link_section = ".iwram"]
+pub unsafe fn dispatch_nested() {
+    let enabled = interrupts::enable().read();
+    let pending = interrupts::sources().read() & enabled;
+    let mut higher = SourceSet::empty();
+    let gba = CriticalGba::new();
+    for &index in PRIORITY.iter() {
+        let source = index_source(index);
+        if pending.contains(source) {
+            if let Some(handler) = HANDLERS[index as usize] {
+                interrupts::irq_acknowledge().write(source);
+                // keep the flags of handlers this one interrupted
+                let bios = interrupts::irq_acknowledge_bios();
+                let flags = bios.read();
+                bios.write(flags | source);
+                interrupts::enable().write(enabled & higher);
+                __cpsr_irq_restore(0);
+                handler(&gba);
+                __cpsr_irq_disable();
+                interrupts::enable().write(enabled);
+            }
+        }
+        higher.insert(source);
+    }
+}
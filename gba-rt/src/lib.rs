@@ -1,30 +1,70 @@
-#![feature(compiler_builtins_lib, allocator, const_fn, cfg_target_has_atomic, lang_items, linkage)]
-#![allocator]
+#![feature(compiler_builtins_lib, const_fn, cfg_target_has_atomic, lang_items, linkage)]
+#![cfg_attr(not(feature = "global-allocator"), feature(allocator))]
+#![cfg_attr(not(feature = "global-allocator"), allocator)]
 #![no_std]
-use core::ptr;
+use core::{cmp, mem, ptr, slice};
 extern crate compiler_builtins;
 
 extern crate gba_hw;
 
-extern crate linked_list_allocator;
-use linked_list_allocator::*;
+extern crate gba_heap;
+use gba_heap::{block_size, Heap};
 
 extern "C" {
-    static __eheap_start: usize;
-    static __eheap_end: usize;
+    static __heap_start: usize;
+    static __heap_end: usize;
 }
 
-static mut EHEAP: Heap = Heap::empty();
+static mut HEAP: Heap = gba_heap::EMPTY;
 
 pub unsafe fn init_heap() {
-    EHEAP.init(
-        (&__eheap_start as *const usize) as usize,
-        ((&__eheap_end as *const usize) as usize) -
-        ((&__eheap_start as *const usize) as usize));
+    HEAP.init(
+        (&__heap_start as *const usize) as usize,
+        ((&__heap_end as *const usize) as usize) -
+        ((&__heap_start as *const usize) as usize));
 }
 
 extern crate gba_bios;
 
+extern crate gba_gpio;
+
+extern crate gba_save;
+
+/// Places a complete cartridge header, given as a `[u8; 192]`
+/// expression, at the start of the cartridge in place of the default
+/// one.  The header's branch must jump to 0xC0, where `_init` is.
+///
+/// `gba-cartridge` can generate the header, checksum and all, from the
+/// `[package.metadata.gba]` table of the game's `Cargo.toml` in its
+/// build script:
+///
+/// ```ignore
+/// #[macro_use]
+/// extern crate gba_rt;
+///
+/// rom_header!(include!(concat!(env!("OUT_DIR"), "/header.rs")));
+/// ```
+#[macro_export]
+macro_rules! rom_header {
+    ($header:expr) => {
+        #[link_section = ".header"]
+        #[no_mangle]
+        pub static _header: [u8; 192] = $header;
+    }
+}
+
+pub mod debug;
+pub mod gba;
+pub mod gpio;
+pub mod interrupts;
+#[cfg(feature = "multiboot")]
+pub mod multiboot;
+mod panic;
+pub mod save;
+pub mod sync;
+
+pub use gba::{Gba, CriticalGba};
+
 #[cfg(target_has_atomic = "ptr")]
 compile_error!("Something's gone wrong!  Arm7tdmi has no atomics");
 
@@ -33,26 +73,99 @@ compile_error!("This crate is specific to arm");
 
 #[no_mangle]
 pub unsafe fn __rust_allocate(size: usize, align: usize) -> *mut u8 {
-    EHEAP.allocate_first_fit(size, align).unwrap_or(ptr::null_mut())
+    HEAP.allocate(size, align)
 }
 
 #[no_mangle]
-pub unsafe fn __rust_deallocate(ptr: *mut u8, size: usize, align: usize) {
-    EHEAP.deallocate(ptr, size, align)
+pub unsafe fn __rust_deallocate(ptr: *mut u8, size: usize, _align: usize) {
+    HEAP.deallocate(ptr, size)
 }
 
-extern "C" {
-    pub fn __rust_usable_size(size: usize, align: usize) -> usize;
-    pub fn __rust_reallocate(ptr: *mut u8, size: usize, new_size: usize, align: usize) -> *mut u8;
-    pub fn __rust_reallocate_inplace(ptr: *mut u8,
-                                     size: usize,
-                                     new_size: usize,
-                                     align: usize)
-                                     -> usize;
+/// Blocks are whole units of two words, which any size up to this
+/// frees in full.
+#[no_mangle]
+pub fn __rust_usable_size(size: usize, _align: usize) -> usize {
+    block_size(size)
+}
+
+/// Blocks grow into the free memory right after them when there's
+/// enough, and shrink by giving their end back to the heap.
+#[no_mangle]
+pub unsafe fn __rust_reallocate_inplace(ptr: *mut u8,
+                                        size: usize,
+                                        new_size: usize,
+                                        _align: usize)
+                                        -> usize {
+    if new_size <= size {
+        HEAP.shrink_in_place(ptr, size, new_size);
+        block_size(new_size)
+    } else if HEAP.grow_in_place(ptr, size, new_size) {
+        block_size(new_size)
+    } else {
+        block_size(size)
+    }
+}
+
+/// Falls back to moving the block, copying whole words with the BIOS
+/// when both blocks are word aligned, which the heap's blocks always
+/// are.
+#[no_mangle]
+pub unsafe fn __rust_reallocate(ptr: *mut u8,
+                                size: usize,
+                                new_size: usize,
+                                align: usize)
+                                -> *mut u8 {
+    if __rust_reallocate_inplace(ptr, size, new_size, align) >= new_size {
+        return ptr;
+    }
+    let new_ptr = __rust_allocate(new_size, align);
+    if new_ptr.is_null() {
+        return new_ptr;
+    }
+
+    let len = cmp::min(size, new_size);
+    let word = mem::size_of::<u32>();
+    if (ptr as usize | new_ptr as usize) & (word - 1) == 0 {
+        // both blocks are whole words long, so the last one can be
+        // copied whole
+        let words = (len + word - 1) / word;
+        gba_bios::fast_copy(slice::from_raw_parts(ptr as *const u32, words),
+                            slice::from_raw_parts_mut(new_ptr as *mut u32, words));
+    } else {
+        ptr::copy_nonoverlapping(ptr, new_ptr, len);
+    }
+    __rust_deallocate(ptr, size, align);
+    new_ptr
+}
+
+/// The heap as a `GlobalAlloc`, for compilers that have replaced the
+/// `allocator` attribute with `#[global_allocator]`.
+#[cfg(feature = "global-allocator")]
+pub struct Allocator;
+
+#[cfg(feature = "global-allocator")]
+unsafe impl core::alloc::GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+        __rust_allocate(layout.size(), layout.align())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+        __rust_deallocate(ptr, layout.size(), layout.align())
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: core::alloc::Layout, new_size: usize) -> *mut u8 {
+        __rust_reallocate(ptr, layout.size(), new_size, layout.align())
+    }
 }
 
+#[cfg(feature = "global-allocator")]
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
 #[lang = "eh_personality"] extern fn eh_personality() {}
-#[lang="panic_fmt"] pub fn panic_fmt(_fmt: &core::fmt::Arguments, _file_line: &(&'static str, usize)) -> ! { loop { } }
+#[lang="panic_fmt"] pub fn panic_fmt(fmt: &core::fmt::Arguments, file_line: &(&'static str, usize)) -> ! {
+    panic::show(fmt, file_line.0, file_line.1)
+}
 
 #[no_mangle]
 pub fn __aeabi_unwind_cpp_pr0() {}
@@ -77,21 +190,39 @@ extern "C" {
     static __ewram_end: u8;
     static __ewram_lma: u8;
 
+    static mut __bss_start: u8;
+    static __bss_end: u8;
+
+    static mut __iwrambss_start: u8;
+    static __iwrambss_end: u8;
+
     fn __usr_irq_handler();
 }
 
-#[allow(improper_ctypes)]
-extern "C" {
-    fn main() -> !;
+extern "Rust" {
+    fn main(gba: Gba) -> !;
 }
 
 unsafe fn load_section(start: &mut u8, end: &u8, lma: &u8) {
-    let len_bytes = (end as *const u8 as usize) - (start as *const u8 as usize);
+    // In multiboot images, sections in EWRAM are already in place.
+    if start as *const u8 == lma as *const u8 {
+        return;
+    }
+    let len_words = ((end as *const u8 as usize) - (start as *const u8 as usize)) >> 2;
     gba_bios::fast_copy(
-        core::slice::from_raw_parts(lma as *const u8, len_bytes),
-        core::slice::from_raw_parts_mut(start as *mut u8, len_bytes));
+        core::slice::from_raw_parts(lma as *const u8 as *const u32, len_words),
+        core::slice::from_raw_parts_mut(start as *mut u8 as *mut u32, len_words));
 }
 
+unsafe fn zero_section(start: &mut u8, end: &u8) {
+    let len_words = ((end as *const u8 as usize) - (start as *const u8 as usize)) >> 2;
+    gba_bios::fast_set(
+        0, core::slice::from_raw_parts_mut(start as *mut u8 as *mut u32, len_words));
+}
+
+// Generated from the memory layout by the build script.
+include!(concat!(env!("OUT_DIR"), "/sections.rs"));
+
 #[no_mangle]
 pub unsafe fn _start() -> ! {
     let usr_irq_ptr = 0x03007FFC as *mut usize;
@@ -99,7 +230,10 @@ pub unsafe fn _start() -> ! {
     load_section(&mut __ewram_start, &__ewram_end, &__ewram_lma);
     load_section(&mut __iwram_start, &__iwram_end, &__iwram_lma);
     load_section(&mut __data_start, &__data_end, &__data_lma);
-    main()
+    load_extra_sections();
+    zero_section(&mut __bss_start, &__bss_end);
+    zero_section(&mut __iwrambss_start, &__iwrambss_end);
+    main(Gba::take().unwrap())
 }
 
 #[link_section=".iwram"]
@@ -108,12 +242,14 @@ pub unsafe fn _irq_handler() {
     handle_interrupts()
 }
 
+#[link_section=".iwram"]
+#[no_mangle]
+pub unsafe fn _irq_handler_nested() {
+    interrupts::dispatch_nested()
+}
+
 #[linkage = "weak"]
 #[no_mangle]
 pub fn handle_interrupts() {
-    let handled_interrupts = gba_hw::interrupts::SourceSet::all();
-    unsafe {
-        gba_hw::interrupts::irq_acknowledge().write(handled_interrupts);
-        gba_hw::interrupts::irq_acknowledge_bios().write(handled_interrupts);
-    }
+    interrupts::dispatch()
 }
@@ -0,0 +1,38 @@
+//! What the BIOS told a multiboot image about how it was loaded.
+
+extern "C" {
+    static __boot_mode: u8;
+    static __slave_id: u8;
+}
+
+/// How the image was sent.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BootMode {
+    /// Over the JOY bus, e.g. from a GameCube.
+    JoyBus,
+
+    /// Normal mode serial transfer.
+    Normal,
+
+    /// Multiplayer mode serial transfer.
+    Multiplay,
+
+    /// Any other value the BIOS left.
+    Unknown(u8),
+}
+
+/// How the image was sent, as filled in by the BIOS at 0x020000C4.
+pub fn boot_mode() -> BootMode {
+    match unsafe { ::core::ptr::read_volatile(&__boot_mode) } {
+        1 => BootMode::JoyBus,
+        2 => BootMode::Normal,
+        3 => BootMode::Multiplay,
+        mode => BootMode::Unknown(mode),
+    }
+}
+
+/// This unit's ID in multiplayer mode, 1-3, as filled in by the BIOS at
+/// 0x020000C5.
+pub fn slave_id() -> u8 {
+    unsafe { ::core::ptr::read_volatile(&__slave_id) }
+}
@@ -0,0 +1,21 @@
+    @ The multiboot header fields after the cartridge header.  The BIOS
+    @ enters at 0xC0, and fills in the boot mode and slave ID, so the
+    @ entry branches over them.
+    .section .multiboot,#alloc
+    .global _multiboot
+_multiboot:
+    .arm
+    b (_init)
+
+    .global __boot_mode
+__boot_mode:
+    .byte 0
+    .global __slave_id
+__slave_id:
+    .byte 0
+
+    @ reserved
+    .fill 26, 1, 0
+
+    @ JOY bus entry
+    b (_init)
@@ -0,0 +1,221 @@
+//! The screen shown when the game panics.
+//!
+//! Instead of freezing on whatever was being displayed, a panic
+//! switches to mode 3 and shows the message, where it happened and the
+//! interrupt and display registers as they were, then restarts the game
+//! once A, B, Start and Select are held together.  The message is
+//! logged as an error too, for emulators with debug output.
+//!
+//! With the `backtrace` feature the return addresses of the calls that
+//! led to the panic are shown too, innermost first, starting with the
+//! panic handler's own.  They're found by following frame pointers, so
+//! the game has to be built with `-C force-frame-pointers=yes`.
+
+use core::fmt::{self, Write};
+use core::ptr;
+use debug::{self, Level};
+use gba_bios;
+use gba_hw::{dma, interrupts, keypad, sound, video};
+use gba_hw::interrupts::MasterEnable;
+use gba_hw::keypad::{A, B, SELECT, START};
+use gba_hw::video::{DisplayControlWrite, BG2};
+
+const VRAM: *mut u16 = 0x600_0000 as *mut u16;
+const DISPCNT: *const u16 = 0x400_0000 as *const u16;
+const BG2CNT: *mut u16 = 0x400_000C as *mut u16;
+const MOSAIC: *mut u16 = 0x400_004C as *mut u16;
+const BLDCNT: *mut u16 = 0x400_0050 as *mut u16;
+
+const WIDTH: usize = 240;
+const HEIGHT: usize = 160;
+
+// Each character is 3x5 pixels in a 4x6 cell.
+const CELL_WIDTH: usize = 4;
+const CELL_HEIGHT: usize = 6;
+const COLUMNS: usize = WIDTH / CELL_WIDTH;
+const ROWS: usize = HEIGHT / CELL_HEIGHT;
+
+const BACKGROUND: u16 = 0x2800;
+const TEXT: u16 = 0x7FFF;
+const HEADING: u16 = 0x03FF;
+
+/// Return addresses shown with the `backtrace` feature, which fill the
+/// rows left once the rest is shown.
+#[cfg(feature = "backtrace")]
+const MAX_FRAMES: usize = 36;
+
+/// Printable ASCII from space to `~`, one glyph each.  Each octal
+/// digit is a row, top first, with 4 the leftmost pixel.
+static FONT: [u16; 95] = [
+    0o00000, 0o22202, 0o55000, 0o57575, 0o36236, 0o51245, 0o25253, 0o22000,
+    0o12221, 0o42224, 0o05250, 0o02720, 0o00024, 0o00700, 0o00002, 0o11244,
+    0o75557, 0o26227, 0o71747, 0o71317, 0o55711, 0o74717, 0o74757, 0o71122,
+    0o75757, 0o75717, 0o02020, 0o02024, 0o12421, 0o07070, 0o42124, 0o71202,
+    0o25743, 0o25755, 0o65656, 0o34443, 0o65556, 0o74647, 0o74644, 0o34553,
+    0o55755, 0o72227, 0o11152, 0o55655, 0o44447, 0o57755, 0o65555, 0o25552,
+    0o65644, 0o25563, 0o65655, 0o34216, 0o72222, 0o55557, 0o55552, 0o55775,
+    0o55255, 0o55222, 0o71247, 0o64446, 0o44211, 0o31113, 0o25000, 0o00007,
+    0o42000, 0o03553, 0o46556, 0o03443, 0o13553, 0o02563, 0o12722, 0o35316,
+    0o46555, 0o20222, 0o10152, 0o45655, 0o62227, 0o06755, 0o06555, 0o02552,
+    0o06564, 0o03531, 0o03444, 0o03616, 0o27221, 0o05553, 0o05552, 0o05777,
+    0o05225, 0o05316, 0o07367, 0o32623, 0o22222, 0o62326, 0o03600,
+];
+
+static mut PANICKING: bool = false;
+
+/// Text written a character cell at a time, wrapping at the edge of
+/// the screen.  Rows past `rows` are dropped.
+struct Screen {
+    column: usize,
+    row: usize,
+    rows: usize,
+    color: u16,
+}
+
+impl Screen {
+    fn newline(&mut self) {
+        self.column = 0;
+        self.row += 1;
+    }
+
+    fn draw(&mut self, c: char) {
+        if self.column == COLUMNS {
+            self.newline();
+        }
+        if self.row < self.rows {
+            let glyph = match c {
+                ' '...'~' => FONT[c as usize - ' ' as usize],
+                _ => FONT['?' as usize - ' ' as usize],
+            };
+            for y in 0..5 {
+                for x in 0..3 {
+                    if glyph >> ((4 - y) * 3 + 2 - x) & 1 != 0 {
+                        let offset = (self.row * CELL_HEIGHT + y) * WIDTH +
+                            self.column * CELL_WIDTH + x;
+                        unsafe { ptr::write_volatile(VRAM.offset(offset as isize), self.color) };
+                    }
+                }
+            }
+        }
+        self.column += 1;
+    }
+}
+
+impl Write for Screen {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if c == '\n' {
+                self.newline();
+            } else {
+                self.draw(c);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Puts the display in mode 3 with nothing that could distort it, and
+// stops anything still running in the background.
+unsafe fn reset_hardware() {
+    interrupts::master_enable().write(MasterEnable::InterruptsDisabled);
+    for channel in &mut [dma::channel0(), dma::channel1(), dma::channel2(), dma::channel3()] {
+        channel.control.update(|mut w| { w.disable(); w });
+    }
+    sound::master_control().write(0);
+
+    video::display_control().write(
+        DisplayControlWrite::default()
+            .set_video_mode(3)
+            .set_display_layers(BG2));
+    ptr::write_volatile(BG2CNT, 0);
+    ptr::write_volatile(MOSAIC, 0);
+    ptr::write_volatile(BLDCNT, 0);
+    let affine = video::bg2_affine();
+    affine.pa.write(0x100);
+    affine.pb.write(0);
+    affine.pc.write(0);
+    affine.pd.write(0x100);
+    affine.x.write(0);
+    affine.y.write(0);
+
+    for i in 0..WIDTH * HEIGHT {
+        ptr::write_volatile(VRAM.offset(i as isize), BACKGROUND);
+    }
+}
+
+#[cfg(feature = "backtrace")]
+fn on_stack(frame: usize) -> bool {
+    let iwram = frame >= 0x300_0000 && frame < 0x300_8000 - 8;
+    let ewram = frame >= 0x200_0000 && frame < 0x204_0000 - 8;
+    frame & 3 == 0 && (iwram || ewram)
+}
+
+#[cfg(feature = "backtrace")]
+fn backtrace(screen: &mut Screen) {
+    extern "C" {
+        fn __frame_pointer() -> usize;
+    }
+    let _ = writeln!(screen, "\nBacktrace:");
+    // each frame starts with the caller's frame pointer, then the
+    // return address
+    let mut frame = unsafe { __frame_pointer() };
+    for _ in 0..MAX_FRAMES {
+        if !on_stack(frame) {
+            break;
+        }
+        let (next, lr) = unsafe {
+            (ptr::read(frame as *const usize), ptr::read((frame + 4) as *const usize))
+        };
+        let _ = write!(screen, " {:08X}", lr & !1);
+        // the stack grows down, so callers' frames are higher
+        if next <= frame {
+            break;
+        }
+        frame = next;
+    }
+}
+
+#[cfg(not(feature = "backtrace"))]
+fn backtrace(_screen: &mut Screen) {}
+
+/// Shows the panic, then waits for the reset keys.
+pub fn show(message: &fmt::Arguments, file: &str, line: usize) -> ! {
+    unsafe {
+        // a panic while showing a panic just stops
+        if PANICKING {
+            loop {}
+        }
+        PANICKING = true;
+    }
+
+    let (enable, sources, master_enable, display_control) = unsafe {
+        (interrupts::enable().read().bits(),
+         interrupts::sources().read().bits(),
+         interrupts::master_enable().read() == MasterEnable::InterruptsEnabled,
+         ptr::read_volatile(DISPCNT))
+    };
+    debug::log(Level::Error, format_args!("Panicked at {}:{}: {}", file, line, message));
+    unsafe { reset_hardware() };
+
+    let mut screen = Screen { column: 0, row: 0, rows: ROWS - 2, color: HEADING };
+    let _ = writeln!(screen, "Panicked at {}:{}", file, line);
+    screen.color = TEXT;
+    let _ = writeln!(screen, "{}", message);
+    let _ = writeln!(screen,
+                     "\nIE {:04X}  IF {:04X}  IME {}  DISPCNT {:04X}",
+                     enable,
+                     sources,
+                     master_enable as u8,
+                     display_control);
+    backtrace(&mut screen);
+
+    let mut prompt = Screen { column: 0, row: ROWS - 1, rows: ROWS, color: HEADING };
+    let _ = prompt.write_str("Hold A+B+START+SELECT to restart");
+
+    let reset_keys = A | B | SELECT | START;
+    loop {
+        if unsafe { keypad::pressed() }.contains(reset_keys) {
+            gba_bios::soft_reset();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use core::{cmp, ptr};
+use gba::{DmaChannel, Save};
+use gba_hw::dma;
+use gba_hw::system::{self, AccessTime};
+use super::{check_erase, check_range, SaveError, SaveMemory, TIMEOUT_POLLS};
+
+/// Where EEPROM is mapped, which works whatever the size of the ROM.
+const EEPROM: *mut u16 = 0x0DFF_FF00 as *mut u16;
+
+/// EEPROM is read and written in 8 byte blocks.
+const BLOCK_SIZE: usize = 8;
+
+/// Halfwords read back for a block: 4 junk bits, then 64 data bits.
+const READ_BITS: usize = 4 + 64;
+
+/// The two sizes of EEPROM, which can't be told apart by the game, so
+/// have to be known.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum EepromSize {
+    /// 512 bytes, with 6-bit block addresses.
+    Bytes512,
+    /// 8KiB, with 14-bit block addresses.
+    Bytes8K,
+}
+
+impl EepromSize {
+    /// Size in bytes.
+    pub fn len(&self) -> usize {
+        match *self {
+            EepromSize::Bytes512 => 0x200,
+            EepromSize::Bytes8K => 0x2000,
+        }
+    }
+
+    fn address_bits(&self) -> usize {
+        match *self {
+            EepromSize::Bytes512 => 6,
+            EepromSize::Bytes8K => 14,
+        }
+    }
+}
+
+/// EEPROM, which is accessed a bit at a time by DMA 3 transfers.
+pub struct Eeprom {
+    save: Save,
+    dma: DmaChannel,
+    size: EepromSize,
+}
+
+impl Eeprom {
+    /// Takes over save memory, which is EEPROM of `size`, using `dma`
+    /// for transfers.
+    ///
+    /// # Panics
+    ///
+    /// If `dma` isn't channel 3, the only one that can reach EEPROM.
+    pub fn new(save: Save, dma: DmaChannel, size: EepromSize) -> Eeprom {
+        assert!(dma.is_channel3(), "EEPROM needs DMA channel 3");
+        unsafe {
+            system::wait_control().update(|mut w| { w.set_ws2_first(AccessTime::Cycles8); w });
+        }
+        Eeprom { save, dma, size }
+    }
+
+    /// Gives back save memory and the DMA channel.
+    pub fn release(self) -> (Save, DmaChannel) {
+        (self.save, self.dma)
+    }
+
+    // Streams `bits` to EEPROM, one per halfword.
+    fn send(&mut self, bits: &[u16]) {
+        unsafe {
+            self.dma.start(bits.as_ptr() as *const u8,
+                           EEPROM as *mut u8,
+                           bits.len() as u16,
+                           dma::DmaControlWrite::default()
+                               .set_unit_size(dma::UnitSize::HalfWord)
+                               .set_start_timing(dma::StartTiming::Immediately)
+                               .enable());
+        }
+    }
+
+    // Puts the request bits and address of `block` at the start of
+    // `bits`, returning how many were used.
+    fn request(&self, bits: &mut [u16], read: bool, block: usize) -> usize {
+        let address_bits = self.size.address_bits();
+        bits[0] = 1;
+        bits[1] = read as u16;
+        for i in 0..address_bits {
+            bits[2 + i] = (block >> (address_bits - 1 - i)) as u16 & 1;
+        }
+        2 + address_bits
+    }
+
+    fn read_block(&mut self, block: usize, data: &mut [u8; BLOCK_SIZE]) {
+        let mut bits = [0u16; 2 + 14 + 1];
+        let len = self.request(&mut bits, true, block);
+        self.send(&bits[..len + 1]);
+
+        let mut reply = [0u16; READ_BITS];
+        unsafe {
+            self.dma.start(EEPROM as *const u8,
+                           reply.as_mut_ptr() as *mut u8,
+                           READ_BITS as u16,
+                           dma::DmaControlWrite::default()
+                               .set_unit_size(dma::UnitSize::HalfWord)
+                               .set_start_timing(dma::StartTiming::Immediately)
+                               .enable());
+        }
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = reply[4 + i * 8..4 + (i + 1) * 8]
+                .iter()
+                .fold(0, |byte, &bit| byte << 1 | (bit & 1) as u8);
+        }
+    }
+
+    fn write_block(&mut self, block: usize, data: &[u8; BLOCK_SIZE]) -> Result<(), SaveError> {
+        let mut bits = [0u16; 2 + 14 + 64 + 1];
+        let mut len = self.request(&mut bits, false, block);
+        for &byte in data {
+            for bit in (0..8).rev() {
+                bits[len] = (byte >> bit) as u16 & 1;
+                len += 1;
+            }
+        }
+        self.send(&bits[..len + 1]);
+
+        // EEPROM reads 1 when the write has finished
+        for _ in 0..TIMEOUT_POLLS {
+            if unsafe { ptr::read_volatile(EEPROM) } & 1 != 0 {
+                return Ok(());
+            }
+        }
+        Err(SaveError::Timeout)
+    }
+}
+
+impl SaveMemory for Eeprom {
+    fn len(&self) -> usize {
+        self.size.len()
+    }
+
+    fn erase_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), SaveError> {
+        check_range(self.len(), offset, buf.len())?;
+        let mut block = [0u8; BLOCK_SIZE];
+        for (i, b) in buf.iter_mut().enumerate() {
+            let address = offset + i;
+            if i == 0 || address % BLOCK_SIZE == 0 {
+                self.read_block(address / BLOCK_SIZE, &mut block);
+            }
+            *b = block[address % BLOCK_SIZE];
+        }
+        Ok(())
+    }
+
+    /// Partly written blocks are read first, so the rest of the block
+    /// is kept.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        check_range(self.len(), offset, data.len())?;
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut done = 0;
+        while done < data.len() {
+            let address = offset + done;
+            let within = address % BLOCK_SIZE;
+            let len = cmp::min(BLOCK_SIZE - within, data.len() - done);
+            if len < BLOCK_SIZE {
+                self.read_block(address / BLOCK_SIZE, &mut block);
+            }
+            block[within..within + len].copy_from_slice(&data[done..done + len]);
+            self.write_block(address / BLOCK_SIZE, &block)?;
+            done += len;
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, offset: usize, len: usize) -> Result<(), SaveError> {
+        check_range(self.len(), offset, len)?;
+        check_erase(BLOCK_SIZE, offset, len)?;
+        for block in offset / BLOCK_SIZE..(offset + len) / BLOCK_SIZE {
+            self.write_block(block, &[0xFF; BLOCK_SIZE])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use core::{cmp, ptr};
+use gba::Save;
+use gba_hw::system::{self, AccessTime};
+use super::{check_erase, check_range, poll, read_bytes, write_bytes, SaveError, SaveMemory};
+
+const FLASH: *mut u8 = 0x0E00_0000 as *mut u8;
+
+/// Flash is addressed in 64KiB banks.
+const BANK_SIZE: usize = 0x1_0000;
+
+/// Atmel chips are written a sector at a time, with no erase command.
+const ATMEL_SECTOR_SIZE: usize = 0x80;
+
+const SECTOR_SIZE: usize = 0x1000;
+
+/// The flash chips found in cartridges.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FlashChip {
+    /// 64KiB Atmel AT29LV512.
+    Atmel,
+    /// 64KiB SST SST39VF512.
+    Sst,
+    /// 64KiB Macronix MX29L512.
+    Macronix64K,
+    /// 64KiB Panasonic MN63F805MNP.
+    Panasonic,
+    /// 128KiB Macronix MX29L010.
+    Macronix128K,
+    /// 128KiB Sanyo LE26FV10N1TS.
+    Sanyo,
+}
+
+impl FlashChip {
+    /// The chip with this ID, device in the high byte and manufacturer
+    /// in the low byte.
+    pub fn from_id(id: u16) -> Option<FlashChip> {
+        match id {
+            0x3D1F => Some(FlashChip::Atmel),
+            0xD4BF => Some(FlashChip::Sst),
+            0x1CC2 => Some(FlashChip::Macronix64K),
+            0x1B32 => Some(FlashChip::Panasonic),
+            0x09C2 => Some(FlashChip::Macronix128K),
+            0x1362 => Some(FlashChip::Sanyo),
+            _ => None,
+        }
+    }
+
+    /// Size of the chip in bytes.
+    pub fn len(&self) -> usize {
+        match *self {
+            FlashChip::Macronix128K | FlashChip::Sanyo => 2 * BANK_SIZE,
+            _ => BANK_SIZE,
+        }
+    }
+
+    /// Size of the chip's erasable sectors in bytes.
+    pub fn sector_size(&self) -> usize {
+        match *self {
+            FlashChip::Atmel => ATMEL_SECTOR_SIZE,
+            _ => SECTOR_SIZE,
+        }
+    }
+}
+
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn command(command: u8) {
+    ptr::write_volatile(FLASH.offset(0x5555), 0xAA);
+    ptr::write_volatile(FLASH.offset(0x2AAA), 0x55);
+    ptr::write_volatile(FLASH.offset(0x5555), command);
+}
+
+// Writes `data` from `address` on, which must already be erased
+// except on Atmel chips.
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn program(address: *mut u8, data: &[u8]) {
+    command(0xA0);
+    write_bytes(address, data);
+}
+
+// Erases the sector starting at `address`.
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn erase_sector(address: *mut u8) {
+    command(0x80);
+    ptr::write_volatile(FLASH.offset(0x5555), 0xAA);
+    ptr::write_volatile(FLASH.offset(0x2AAA), 0x55);
+    ptr::write_volatile(address, 0x30);
+}
+
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn switch_bank(bank: u8) {
+    command(0xB0);
+    ptr::write_volatile(FLASH, bank);
+}
+
+/// Flash memory, which is erased in sectors and can then be written a
+/// byte at a time.
+pub struct Flash {
+    save: Save,
+    chip: FlashChip,
+    bank: Option<usize>,
+}
+
+impl Flash {
+    /// Reads the ID of the flash chip.
+    pub fn detect(_save: &mut Save) -> Result<FlashChip, SaveError> {
+        let mut id = [0u8; 2];
+        unsafe {
+            system::wait_control().update(|mut w| { w.set_sram(AccessTime::Cycles8); w });
+            command(0x90);
+            read_bytes(FLASH, &mut id);
+            command(0xF0);
+        }
+        let id = (id[1] as u16) << 8 | id[0] as u16;
+        FlashChip::from_id(id).ok_or(SaveError::UnknownChip(id))
+    }
+
+    /// Takes over save memory, which has a `chip` as found by `detect`.
+    pub fn new(save: Save, chip: FlashChip) -> Flash {
+        unsafe {
+            system::wait_control().update(|mut w| { w.set_sram(AccessTime::Cycles8); w });
+        }
+        Flash { save, chip, bank: None }
+    }
+
+    /// The chip, as passed to `new`.
+    pub fn chip(&self) -> FlashChip {
+        self.chip
+    }
+
+    /// Gives back save memory, e.g. to try another driver.
+    pub fn release(self) -> Save {
+        self.save
+    }
+
+    // Switches to the bank holding `offset`, returning the address of
+    // `offset` within it.
+    fn address(&mut self, offset: usize) -> *mut u8 {
+        let bank = offset / BANK_SIZE;
+        if self.chip.len() > BANK_SIZE && self.bank != Some(bank) {
+            unsafe { switch_bank(bank as u8) };
+            self.bank = Some(bank);
+        }
+        unsafe { FLASH.offset((offset % BANK_SIZE) as isize) }
+    }
+
+    // Writes one whole Atmel sector, erasing it as it goes.
+    fn write_atmel_sector(&mut self, offset: usize, sector: &[u8]) -> Result<(), SaveError> {
+        let address = self.address(offset);
+        unsafe {
+            program(address, sector);
+            let last = sector.len() as isize - 1;
+            poll(address.offset(last), sector[last as usize])
+        }
+    }
+}
+
+impl SaveMemory for Flash {
+    fn len(&self) -> usize {
+        self.chip.len()
+    }
+
+    fn erase_size(&self) -> usize {
+        self.chip.sector_size()
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), SaveError> {
+        check_range(self.len(), offset, buf.len())?;
+        // a read can't cross banks, so split it at each bank boundary
+        let mut done = 0;
+        while done < buf.len() {
+            let start = offset + done;
+            let len = cmp::min(BANK_SIZE - start % BANK_SIZE, buf.len() - done);
+            let address = self.address(start);
+            unsafe { read_bytes(address, &mut buf[done..done + len]) };
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Atmel chips rewrite whole sectors, so don't need erasing first.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        check_range(self.len(), offset, data.len())?;
+        if self.chip == FlashChip::Atmel {
+            let mut sector = [0u8; ATMEL_SECTOR_SIZE];
+            let mut done = 0;
+            while done < data.len() {
+                let start = offset + done;
+                let sector_start = start - start % ATMEL_SECTOR_SIZE;
+                let within = start - sector_start;
+                let len = cmp::min(ATMEL_SECTOR_SIZE - within, data.len() - done);
+                self.read(sector_start, &mut sector)?;
+                sector[within..within + len].copy_from_slice(&data[done..done + len]);
+                self.write_atmel_sector(sector_start, &sector)?;
+                done += len;
+            }
+        } else {
+            for (i, &b) in data.iter().enumerate() {
+                let address = self.address(offset + i);
+                unsafe {
+                    program(address, &[b]);
+                    poll(address, b)?;
+                }
+            }
+        }
+
+        let mut check = [0u8; 1];
+        for (i, &b) in data.iter().enumerate() {
+            self.read(offset + i, &mut check)?;
+            if check[0] != b {
+                return Err(SaveError::Verify);
+            }
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, offset: usize, len: usize) -> Result<(), SaveError> {
+        check_range(self.len(), offset, len)?;
+        let sector_size = self.erase_size();
+        check_erase(sector_size, offset, len)?;
+        let mut sector = offset;
+        while sector < offset + len {
+            if self.chip == FlashChip::Atmel {
+                self.write_atmel_sector(sector, &[0xFF; ATMEL_SECTOR_SIZE])?;
+            } else {
+                let address = self.address(sector);
+                unsafe {
+                    erase_sector(address);
+                    poll(address, 0xFF)?;
+                }
+            }
+            sector += sector_size;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Drivers for the cartridge's save memory.
+//!
+//! Cartridges save to one of 32KiB of battery backed SRAM, 64KiB or
+//! 128KiB of flash, or 512 bytes or 8KiB of EEPROM.  Which one is fixed
+//! by the cartridge, so the game picks the driver, and places the
+//! matching ID string with `save_id!` so emulators and flash carts know
+//! what to provide.
+//!
+//! Every driver takes the `Save` handle from `Gba`, and implements
+//! `SaveMemory` from `gba-save`, whose `Store` keeps checksummed,
+//! versioned save files on top of it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! #[macro_use]
+//! extern crate gba_rt;
+//!
+//! use gba_rt::save::{self, Flash, SaveMemory};
+//!
+//! save_id!(save::FLASH1M_ID);
+//!
+//! #[no_mangle]
+//! pub fn main(mut gba: gba_rt::Gba) -> ! {
+//!     let chip = Flash::detect(&mut gba.save).unwrap();
+//!     let mut flash = Flash::new(gba.save, chip);
+//!
+//!     let mut high_score = [0u8; 4];
+//!     flash.read(0, &mut high_score).unwrap();
+//!     flash.erase(0, flash.erase_size()).unwrap();
+//!     flash.write(0, &[0, 0, 1, 0]).unwrap();
+//!     loop {}
+//! }
+//! # fn main() {}
+//! ```
+
+use core::ptr;
+
+mod eeprom;
+mod flash;
+mod sram;
+
+pub use self::eeprom::{Eeprom, EepromSize};
+pub use self::flash::{Flash, FlashChip};
+pub use self::sram::{Sram, SRAM_SIZE};
+pub use gba_save::{SaveError, SaveMemory};
+
+/// Places a save type ID string, e.g. `save::SRAM_ID`, in the ROM.
+/// The linker script keeps it and puts it on a word boundary, where
+/// emulators and flash carts search for it.
+#[macro_export]
+macro_rules! save_id {
+    ($id:expr) => {
+        #[link_section = ".rodata.save_id"]
+        #[no_mangle]
+        pub static _save_id: [u8; 16] = $id;
+    }
+}
+
+// The ID strings emulators look for, as used by the official
+// libraries, padded to a multiple of 4 bytes.
+
+/// 32KiB SRAM.
+pub const SRAM_ID: [u8; 16] =
+    [b'S', b'R', b'A', b'M', b'_', b'V', b'1', b'1', b'3', 0, 0, 0, 0, 0, 0, 0];
+
+/// 512 byte or 8KiB EEPROM.
+pub const EEPROM_ID: [u8; 16] =
+    [b'E', b'E', b'P', b'R', b'O', b'M', b'_', b'V', b'1', b'2', b'4', 0, 0, 0, 0, 0];
+
+/// 64KiB flash, as used by older games.
+pub const FLASH_ID: [u8; 16] =
+    [b'F', b'L', b'A', b'S', b'H', b'_', b'V', b'1', b'2', b'6', 0, 0, 0, 0, 0, 0];
+
+/// 64KiB flash.
+pub const FLASH512_ID: [u8; 16] =
+    [b'F', b'L', b'A', b'S', b'H', b'5', b'1', b'2', b'_', b'V', b'1', b'3', b'1', 0, 0, 0];
+
+/// 128KiB flash.
+pub const FLASH1M_ID: [u8; 16] =
+    [b'F', b'L', b'A', b'S', b'H', b'1', b'M', b'_', b'V', b'1', b'0', b'3', 0, 0, 0, 0];
+
+/// Polls made waiting for a write or erase before giving up, well
+/// over the slowest chip's sector erase time.
+const TIMEOUT_POLLS: u32 = 0x10_0000;
+
+fn check_range(size: usize, offset: usize, len: usize) -> Result<(), SaveError> {
+    match offset.checked_add(len) {
+        Some(end) if end <= size => Ok(()),
+        _ => Err(SaveError::OutOfRange),
+    }
+}
+
+fn check_erase(erase_size: usize, offset: usize, len: usize) -> Result<(), SaveError> {
+    if offset % erase_size != 0 || len % erase_size != 0 {
+        Err(SaveError::Misaligned)
+    } else {
+        Ok(())
+    }
+}
+
+// Like the official libraries, SRAM and flash are only accessed by
+// code running from IWRAM, not from the cartridge they share a bus
+// with.
+
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn read_bytes(source: *const u8, buf: &mut [u8]) {
+    for (i, b) in buf.iter_mut().enumerate() {
+        *b = ptr::read_volatile(source.offset(i as isize));
+    }
+}
+
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn write_bytes(dest: *mut u8, data: &[u8]) {
+    for (i, &b) in data.iter().enumerate() {
+        ptr::write_volatile(dest.offset(i as isize), b);
+    }
+}
+
+// Waits for the byte at `address` to read back as `value`.
+#[link_section = ".iwram"]
+#[inline(never)]
+unsafe fn poll(address: *const u8, value: u8) -> Result<(), SaveError> {
+    for _ in 0..TIMEOUT_POLLS {
+        if ptr::read_volatile(address) == value {
+            return Ok(());
+        }
+    }
+    Err(SaveError::Timeout)
+}
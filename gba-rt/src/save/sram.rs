@@ -0,0 +1,59 @@
+use gba::Save;
+use gba_hw::system::{self, AccessTime};
+use super::{check_range, read_bytes, write_bytes, SaveError, SaveMemory};
+
+const SRAM: *mut u8 = 0x0E00_0000 as *mut u8;
+
+/// Size of SRAM in bytes.
+pub const SRAM_SIZE: usize = 0x8000;
+
+/// Battery backed SRAM, which can be read and written a byte at a
+/// time.
+pub struct Sram {
+    save: Save,
+}
+
+impl Sram {
+    /// Sets the SRAM wait states and takes over save memory.
+    pub fn new(save: Save) -> Sram {
+        unsafe {
+            system::wait_control().update(|mut w| { w.set_sram(AccessTime::Cycles8); w });
+        }
+        Sram { save }
+    }
+
+    /// Gives back save memory, e.g. to try another driver.
+    pub fn release(self) -> Save {
+        self.save
+    }
+}
+
+impl SaveMemory for Sram {
+    fn len(&self) -> usize {
+        SRAM_SIZE
+    }
+
+    fn erase_size(&self) -> usize {
+        1
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), SaveError> {
+        check_range(SRAM_SIZE, offset, buf.len())?;
+        unsafe { read_bytes(SRAM.offset(offset as isize), buf) };
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        check_range(SRAM_SIZE, offset, data.len())?;
+        unsafe { write_bytes(SRAM.offset(offset as isize), data) };
+        Ok(())
+    }
+
+    fn erase(&mut self, offset: usize, len: usize) -> Result<(), SaveError> {
+        check_range(SRAM_SIZE, offset, len)?;
+        for i in offset..offset + len {
+            unsafe { write_bytes(SRAM.offset(i as isize), &[0xFF]) };
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+//! Sharing state between the main loop and interrupt handlers.
+//!
+//! The ARM7TDMI has no atomic instructions, so the only way to touch
+//! shared data soundly is with interrupts masked.  `critical_section`
+//! masks them and hands out a `CriticalSection` token, and `Mutex`
+//! only gives access to its contents while such a token is alive.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # extern crate core;
+//! extern crate gba_rt;
+//!
+//! use core::cell::RefCell;
+//! use gba_rt::sync::{critical_section, Mutex};
+//!
+//! static FRAMES: Mutex<RefCell<u32>> = Mutex::new(RefCell::new(0));
+//!
+//! // in the vblank handler
+//! fn on_vblank() {
+//!     critical_section(|cs| *FRAMES.borrow(cs).borrow_mut() += 1);
+//! }
+//!
+//! // in the main loop
+//! fn frames() -> u32 {
+//!     critical_section(|cs| *FRAMES.borrow(cs).borrow())
+//! }
+//! # fn main() {}
+//! ```
+
+use core::cell::UnsafeCell;
+use gba_hw::interrupts::{self, MasterEnable};
+
+extern "C" {
+    fn __cpsr_irq_disable() -> u32;
+    fn __cpsr_irq_restore(state: u32);
+}
+
+/// A token proving that interrupts are masked.
+///
+/// Only `critical_section` and `critical_section_cpsr` hand these out,
+/// and only by reference, so a token can't outlive the section.
+pub struct CriticalSection {
+    _private: (),
+}
+
+impl CriticalSection {
+    /// Creates a token without masking anything.
+    ///
+    /// This is unsafe because the caller must guarantee that no
+    /// interrupt handler can run while the token is in use, e.g.
+    /// because the caller is itself a non-nested interrupt handler.
+    #[inline(always)]
+    pub unsafe fn new() -> CriticalSection {
+        CriticalSection { _private: () }
+    }
+}
+
+/// Runs `f` with interrupts masked via `IME`, restoring the previous
+/// `IME` state afterwards.
+///
+/// Critical sections may be nested, and may be entered from interrupt
+/// handlers.
+///
+/// Note that the unsafe BIOS `wait..` functions force `IME` on, so
+/// they must not be called from inside `f`, and nor may `IME` be
+/// turned on with `Interrupts::master_enable`.  The `try_wait..` ones
+/// return an error instead.
+#[inline]
+pub fn critical_section<R, F: FnOnce(&CriticalSection) -> R>(f: F) -> R {
+    unsafe {
+        let ime = interrupts::master_enable();
+        let prev = ime.read();
+        ime.write(MasterEnable::InterruptsDisabled);
+        let result = f(&CriticalSection::new());
+        ime.write(prev);
+        result
+    }
+}
+
+/// Like `critical_section`, but also sets the CPSR I bit for the
+/// duration of `f`.
+///
+/// This is only needed when `f` itself writes `IME`, e.g. code
+/// that calls into a library which enables interrupts.
+#[inline]
+pub fn critical_section_cpsr<R, F: FnOnce(&CriticalSection) -> R>(f: F) -> R {
+    unsafe {
+        let cpsr = __cpsr_irq_disable();
+        let result = critical_section(f);
+        __cpsr_irq_restore(cpsr);
+        result
+    }
+}
+
+/// A container that can only be accessed inside a critical section.
+///
+/// Combine with `RefCell` or `Cell` for mutable state.
+pub struct Mutex<T> {
+    inner: UnsafeCell<T>,
+}
+
+impl<T> Mutex<T> {
+    /// Creates a new mutex, usable in a `static`.
+    pub const fn new(value: T) -> Mutex<T> {
+        Mutex { inner: UnsafeCell::new(value) }
+    }
+
+    /// Borrows the contents for the duration of the critical section.
+    #[inline(always)]
+    pub fn borrow<'cs>(&'cs self, _cs: &'cs CriticalSection) -> &'cs T {
+        unsafe { &*self.inner.get() }
+    }
+
+    /// Mutably borrows the contents, which needs no critical section
+    /// since `&mut self` guarantees exclusive access.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.get() }
+    }
+
+    /// Consumes the mutex, returning its contents.
+    pub fn into_inner(self) -> T {
+        unsafe { self.inner.into_inner() }
+    }
+}
+
+// Access to the contents requires a `CriticalSection`, during which
+// no other context can run.
+unsafe impl<T: Send> Sync for Mutex<T> {}
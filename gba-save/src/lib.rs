@@ -0,0 +1,109 @@
+//! Save files for the game boy advance, over any kind of save memory.
+//!
+//! A `Store` keeps a game's `SaveData` as records in two slots, writing
+//! each save to the slot not holding the last one.  Records carry a
+//! sequence number, a version and a CRC-32, so a save torn by power
+//! loss or a corrupted slot is detected and the other slot is used, and
+//! data saved by an older version of the game can be migrated.
+//!
+//! Save memory drivers implement `SaveMemory`; the ones for the
+//! hardware are in `gba-rt`, and `MemorySave` keeps save memory in RAM
+//! so save code can be tested on the host.
+//!
+//! # Examples
+//!
+//! ```
+//! use gba_save::{MemorySave, Reader, SaveData, SaveMemory, Store, StoreError, Writer};
+//!
+//! #[derive(PartialEq, Debug)]
+//! struct Progress {
+//!     level: u8,
+//!     score: u32,
+//! }
+//!
+//! impl SaveData for Progress {
+//!     fn version() -> u16 {
+//!         2
+//!     }
+//!
+//!     fn write<M: SaveMemory>(&self, writer: &mut Writer<M>) -> Result<(), StoreError> {
+//!         writer.write_u8(self.level)?;
+//!         writer.write_u32(self.score)
+//!     }
+//!
+//!     fn read<M: SaveMemory>(version: u16, reader: &mut Reader<M>) -> Result<Progress, StoreError> {
+//!         match version {
+//!             // version 1 kept the score in 16 bits
+//!             1 => Ok(Progress { level: reader.read_u8()?, score: reader.read_u16()? as u32 }),
+//!             2 => Ok(Progress { level: reader.read_u8()?, score: reader.read_u32()? }),
+//!             _ => Err(StoreError::Version(version)),
+//!         }
+//!     }
+//! }
+//!
+//! let mut store = Store::new(MemorySave::flash([0xFF; 0x2000], 0x1000), 0, 0x1000).unwrap();
+//! assert_eq!(Err(StoreError::Empty), store.load::<Progress>());
+//!
+//! store.save(&Progress { level: 3, score: 70000 }).unwrap();
+//! assert_eq!(Ok(Progress { level: 3, score: 70000 }), store.load());
+//! ```
+
+#![no_std]
+
+mod memory;
+mod store;
+
+pub use memory::MemorySave;
+pub use store::{Reader, SaveData, Store, StoreError, Writer, HEADER_SIZE};
+
+/// Errors from save memory.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SaveError {
+    /// The access runs past the end of the memory.
+    OutOfRange,
+
+    /// An erase doesn't start and end on an `erase_size` boundary.
+    Misaligned,
+
+    /// The memory didn't finish a write or erase in time.
+    Timeout,
+
+    /// Reading back a write gave different data, e.g. because flash
+    /// wasn't erased first.
+    Verify,
+
+    /// A flash chip wasn't recognised, with this ID (device in the high
+    /// byte, manufacturer in the low byte).
+    UnknownChip(u16),
+}
+
+/// Save memory, addressed in bytes from 0.
+pub trait SaveMemory {
+    /// Size of the memory in bytes.
+    fn len(&self) -> usize;
+
+    /// Whether the memory has no bytes at all.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size and alignment of the blocks `erase` works on, a power of
+    /// two.
+    fn erase_size(&self) -> usize;
+
+    /// Reads `buf.len()` bytes starting at `offset`.
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), SaveError>;
+
+    /// Writes `data` starting at `offset`.  Flash can only clear bits,
+    /// so must be erased first.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError>;
+
+    /// Sets `len` bytes starting at `offset` to `0xFF`.  Both must be
+    /// multiples of `erase_size`.
+    fn erase(&mut self, offset: usize, len: usize) -> Result<(), SaveError>;
+}
+
+// Whether `value` is a multiple of `size`, a power of two.
+fn aligned(value: usize, size: usize) -> bool {
+    value & (size - 1) == 0
+}
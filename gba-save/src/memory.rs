@@ -0,0 +1,156 @@
+use {aligned, SaveError, SaveMemory};
+
+/// Save memory kept in RAM, for testing save code on the host.
+///
+/// It can behave like SRAM, or like flash, which is erased in blocks
+/// and whose writes can only clear bits.  `fail_after` simulates
+/// losing power part way through a save.
+///
+/// # Examples
+///
+/// A save torn by power loss leaves the last one in place:
+///
+/// ```
+/// use gba_save::{MemorySave, Reader, SaveData, SaveMemory, Store, StoreError, Writer,
+///                HEADER_SIZE};
+///
+/// #[derive(PartialEq, Debug)]
+/// struct Coins(u32);
+///
+/// impl SaveData for Coins {
+///     fn version() -> u16 {
+///         1
+///     }
+///
+///     fn write<M: SaveMemory>(&self, writer: &mut Writer<M>) -> Result<(), StoreError> {
+///         writer.write_u32(self.0)
+///     }
+///
+///     fn read<M: SaveMemory>(_version: u16, reader: &mut Reader<M>) -> Result<Coins, StoreError> {
+///         reader.read_u32().map(Coins)
+///     }
+/// }
+///
+/// let mut store = Store::new(MemorySave::new([0; 64]), 0, 32).unwrap();
+/// store.save(&Coins(10)).unwrap();
+///
+/// // the power goes at each point while erasing the other slot, then
+/// // writing the record
+/// for bytes in 0..32 + 4 + HEADER_SIZE {
+///     store.memory().fail_after(bytes);
+///     assert!(store.save(&Coins(20)).is_err());
+///     store.memory().restore_power();
+///     assert_eq!(Ok(Coins(10)), store.load());
+/// }
+///
+/// store.save(&Coins(20)).unwrap();
+/// assert_eq!(Ok(Coins(20)), store.load());
+///
+/// // a flipped bit in the newest save is caught too
+/// store.memory().data_mut()[32 + 6] ^= 0x10;
+/// assert_eq!(Ok(Coins(10)), store.load());
+/// ```
+pub struct MemorySave<T> {
+    data: T,
+    erase_size: usize,
+    flash: bool,
+    budget: Option<usize>,
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> MemorySave<T> {
+    /// Memory like SRAM, which can be written a byte at a time.
+    pub fn new(data: T) -> MemorySave<T> {
+        MemorySave { data, erase_size: 1, flash: false, budget: None }
+    }
+
+    /// Memory like flash, erased in `erase_size` blocks, which must be a
+    /// power of two.
+    pub fn flash(data: T, erase_size: usize) -> MemorySave<T> {
+        MemorySave { data, erase_size, flash: true, budget: None }
+    }
+
+    /// Loses power after `bytes` more bytes have been written or
+    /// erased.  The rest of that write or erase is lost, and it and
+    /// every later one fail with `SaveError::Timeout`, until
+    /// `restore_power`.
+    pub fn fail_after(&mut self, bytes: usize) {
+        self.budget = Some(bytes);
+    }
+
+    /// Turns the power back on.
+    pub fn restore_power(&mut self) {
+        self.budget = None;
+    }
+
+    /// The contents of the memory.
+    pub fn data(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    /// The contents of the memory, e.g. to corrupt them.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        self.data.as_mut()
+    }
+
+    /// Gives back the contents.
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+
+    fn check(&self, offset: usize, len: usize) -> Result<(), SaveError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.len() => Ok(()),
+            _ => Err(SaveError::OutOfRange),
+        }
+    }
+
+    // Stores `f(old)` into each byte, until the power runs out.
+    fn store<F: Fn(usize, u8) -> u8>(&mut self, offset: usize, len: usize, f: F)
+                                    -> Result<(), SaveError> {
+        for i in 0..len {
+            if let Some(ref mut budget) = self.budget {
+                if *budget == 0 {
+                    return Err(SaveError::Timeout);
+                }
+                *budget -= 1;
+            }
+            let byte = &mut self.data.as_mut()[offset + i];
+            *byte = f(i, *byte);
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> SaveMemory for MemorySave<T> {
+    fn len(&self) -> usize {
+        self.data.as_ref().len()
+    }
+
+    fn erase_size(&self) -> usize {
+        self.erase_size
+    }
+
+    fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), SaveError> {
+        self.check(offset, buf.len())?;
+        buf.copy_from_slice(&self.data.as_ref()[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), SaveError> {
+        self.check(offset, data.len())?;
+        let flash = self.flash;
+        self.store(offset, data.len(), |i, old| if flash { old & data[i] } else { data[i] })?;
+        if self.data.as_ref()[offset..offset + data.len()] != *data {
+            return Err(SaveError::Verify);
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, offset: usize, len: usize) -> Result<(), SaveError> {
+        self.check(offset, len)?;
+        if !aligned(offset, self.erase_size) || !aligned(len, self.erase_size) {
+            return Err(SaveError::Misaligned);
+        }
+        self.store(offset, len, |_, _| 0xFF)
+    }
+}
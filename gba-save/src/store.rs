@@ -0,0 +1,365 @@
+use core::cmp;
+use {aligned, SaveError, SaveMemory};
+
+/// Marks the start of a record.
+const MAGIC: &[u8; 4] = b"GSAV";
+
+/// Size of a record's header: magic, sequence number, length,
+/// version, two reserved bytes and CRC-32.
+pub const HEADER_SIZE: usize = 20;
+
+/// Size of the header fields covered by the CRC.
+const CHECKED_HEADER_SIZE: usize = 16;
+
+/// Bytes buffered by `Writer` and read at a time when checking a
+/// record.
+const CHUNK_SIZE: usize = 32;
+
+/// Errors from saving and loading.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StoreError {
+    /// The save memory failed.
+    Memory(SaveError),
+
+    /// Nothing has been saved yet.
+    Empty,
+
+    /// Both slots hold torn or corrupted records.
+    Corrupt,
+
+    /// The record doesn't fit in a slot.
+    TooLarge,
+
+    /// `SaveData::read` ran past the end of the record, or found data
+    /// it doesn't understand.
+    Format,
+
+    /// The record was written by a version `SaveData::read` doesn't
+    /// know.
+    Version(u16),
+
+    /// The slots aren't aligned to the memory's erase size, or don't fit
+    /// in it.
+    Layout,
+}
+
+impl From<SaveError> for StoreError {
+    fn from(error: SaveError) -> StoreError {
+        StoreError::Memory(error)
+    }
+}
+
+/// Game state that can be saved.
+pub trait SaveData: Sized {
+    /// The version `write` writes, stored with each record.
+    fn version() -> u16;
+
+    /// Writes the state.
+    fn write<M: SaveMemory>(&self, writer: &mut Writer<M>) -> Result<(), StoreError>;
+
+    /// Reads a record written at `version`, migrating it if it's from
+    /// an older version.
+    fn read<M: SaveMemory>(version: u16, reader: &mut Reader<M>) -> Result<Self, StoreError>;
+}
+
+// CRC-32, as used by zip, computed a bit at a time to save ROM.
+struct Crc32(u32);
+
+impl Crc32 {
+    fn new() -> Crc32 {
+        Crc32(!0)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= byte as u32;
+            for _ in 0..8 {
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & (!(self.0 & 1)).wrapping_add(1));
+            }
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    data[offset] as u16 | (data[offset + 1] as u16) << 8
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u16_at(data, offset) as u32 | (u16_at(data, offset + 2) as u32) << 16
+}
+
+fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+    for i in 0..4 {
+        data[offset + i] = (value >> (i * 8)) as u8;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Header {
+    sequence: u32,
+    length: usize,
+    version: u16,
+    checksum: u32,
+}
+
+impl Header {
+    fn bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[..4].copy_from_slice(MAGIC);
+        put_u32(&mut bytes, 4, self.sequence);
+        put_u32(&mut bytes, 8, self.length as u32);
+        bytes[12] = self.version as u8;
+        bytes[13] = (self.version >> 8) as u8;
+        put_u32(&mut bytes, 16, self.checksum);
+        bytes
+    }
+}
+
+// What's in a slot.
+enum Slot {
+    Blank,
+    Corrupt,
+    Valid(Header),
+}
+
+// Whether sequence number `a` comes after `b`, allowing for wrapping.
+fn newer(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+/// Writes a record's contents, little-endian.
+pub struct Writer<'a, M: 'a> {
+    memory: &'a mut M,
+    start: usize,
+    capacity: usize,
+    length: usize,
+    crc: Crc32,
+    buffer: [u8; CHUNK_SIZE],
+    buffered: usize,
+}
+
+impl<'a, M: SaveMemory> Writer<'a, M> {
+    fn flush(&mut self) -> Result<(), StoreError> {
+        let offset = self.start + self.length - self.buffered;
+        self.memory.write(offset, &self.buffer[..self.buffered])?;
+        self.buffered = 0;
+        Ok(())
+    }
+
+    /// Writes `data` as it is.
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<(), StoreError> {
+        if data.len() > self.capacity - self.length {
+            return Err(StoreError::TooLarge);
+        }
+        self.crc.update(data);
+        for &byte in data {
+            self.buffer[self.buffered] = byte;
+            self.buffered += 1;
+            self.length += 1;
+            if self.buffered == CHUNK_SIZE {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> Result<(), StoreError> {
+        self.write_bytes(&[value])
+    }
+
+    pub fn write_u16(&mut self, value: u16) -> Result<(), StoreError> {
+        self.write_bytes(&[value as u8, (value >> 8) as u8])
+    }
+
+    pub fn write_u32(&mut self, value: u32) -> Result<(), StoreError> {
+        let mut bytes = [0u8; 4];
+        put_u32(&mut bytes, 0, value);
+        self.write_bytes(&bytes)
+    }
+}
+
+/// Reads a record's contents, little-endian.
+pub struct Reader<'a, M: 'a> {
+    memory: &'a mut M,
+    start: usize,
+    length: usize,
+    position: usize,
+}
+
+impl<'a, M: SaveMemory> Reader<'a, M> {
+    /// Bytes left in the record.
+    pub fn remaining(&self) -> usize {
+        self.length - self.position
+    }
+
+    /// Fills `buf` with the next bytes.
+    pub fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), StoreError> {
+        if buf.len() > self.remaining() {
+            return Err(StoreError::Format);
+        }
+        self.memory.read(self.start + self.position, buf)?;
+        self.position += buf.len();
+        Ok(())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StoreError> {
+        let mut bytes = [0u8; 1];
+        self.read_bytes(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StoreError> {
+        let mut bytes = [0u8; 2];
+        self.read_bytes(&mut bytes)?;
+        Ok(u16_at(&bytes, 0))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, StoreError> {
+        let mut bytes = [0u8; 4];
+        self.read_bytes(&mut bytes)?;
+        Ok(u32_at(&bytes, 0))
+    }
+}
+
+/// Saves and loads records in two slots of save memory.
+pub struct Store<M> {
+    memory: M,
+    offset: usize,
+    slot_size: usize,
+}
+
+impl<M: SaveMemory> Store<M> {
+    /// Uses two slots of `slot_size` bytes, starting at `offset` in
+    /// `memory`.  Both must be multiples of the memory's erase size.
+    pub fn new(memory: M, offset: usize, slot_size: usize) -> Result<Store<M>, StoreError> {
+        let erase_size = memory.erase_size();
+        let fits = match slot_size.checked_mul(2).and_then(|size| size.checked_add(offset)) {
+            Some(end) => end <= memory.len(),
+            None => false,
+        };
+        if !fits || slot_size <= HEADER_SIZE || !aligned(offset, erase_size) ||
+            !aligned(slot_size, erase_size) {
+            return Err(StoreError::Layout);
+        }
+        Ok(Store { memory, offset, slot_size })
+    }
+
+    /// The save memory, e.g. to simulate power loss with `MemorySave`.
+    pub fn memory(&mut self) -> &mut M {
+        &mut self.memory
+    }
+
+    /// Gives back the save memory.
+    pub fn into_inner(self) -> M {
+        self.memory
+    }
+
+    fn slot_offset(&self, slot: usize) -> usize {
+        self.offset + slot * self.slot_size
+    }
+
+    fn check_slot(&mut self, slot: usize) -> Result<Slot, StoreError> {
+        let start = self.slot_offset(slot);
+        let mut bytes = [0u8; HEADER_SIZE];
+        self.memory.read(start, &mut bytes)?;
+        if &bytes[..4] != MAGIC {
+            return Ok(Slot::Blank);
+        }
+        let header = Header {
+            sequence: u32_at(&bytes, 4),
+            length: u32_at(&bytes, 8) as usize,
+            version: u16_at(&bytes, 12),
+            checksum: u32_at(&bytes, 16),
+        };
+        if header.length > self.slot_size - HEADER_SIZE {
+            return Ok(Slot::Corrupt);
+        }
+
+        let mut crc = Crc32::new();
+        let mut chunk = [0u8; CHUNK_SIZE];
+        let mut done = 0;
+        while done < header.length {
+            let len = cmp::min(header.length - done, CHUNK_SIZE);
+            self.memory.read(start + HEADER_SIZE + done, &mut chunk[..len])?;
+            crc.update(&chunk[..len]);
+            done += len;
+        }
+        crc.update(&bytes[..CHECKED_HEADER_SIZE]);
+        if crc.finish() == header.checksum {
+            Ok(Slot::Valid(header))
+        } else {
+            Ok(Slot::Corrupt)
+        }
+    }
+
+    // The slot and header of the newest valid record, if any, and
+    // whether any slot held a record that wasn't valid.
+    fn newest(&mut self) -> Result<(Option<(usize, Header)>, bool), StoreError> {
+        let mut newest: Option<(usize, Header)> = None;
+        let mut corrupt = false;
+        for slot in 0..2 {
+            match self.check_slot(slot)? {
+                Slot::Blank => {}
+                Slot::Corrupt => corrupt = true,
+                Slot::Valid(header) => match newest {
+                    Some((_, other)) if !newer(header.sequence, other.sequence) => {}
+                    _ => newest = Some((slot, header)),
+                },
+            }
+        }
+        Ok((newest, corrupt))
+    }
+
+    /// Loads the newest record that isn't torn or corrupted.
+    pub fn load<T: SaveData>(&mut self) -> Result<T, StoreError> {
+        let (slot, header) = match self.newest()? {
+            (Some(newest), _) => newest,
+            (None, true) => return Err(StoreError::Corrupt),
+            (None, false) => return Err(StoreError::Empty),
+        };
+        let mut reader = Reader {
+            start: self.slot_offset(slot) + HEADER_SIZE,
+            memory: &mut self.memory,
+            length: header.length,
+            position: 0,
+        };
+        T::read(header.version, &mut reader)
+    }
+
+    /// Saves `data` to the slot not holding the newest record, so that
+    /// record survives if this save is interrupted.
+    pub fn save<T: SaveData>(&mut self, data: &T) -> Result<(), StoreError> {
+        let (slot, sequence) = match self.newest()?.0 {
+            Some((slot, header)) => (1 - slot, header.sequence.wrapping_add(1)),
+            None => (0, 0),
+        };
+        let start = self.slot_offset(slot);
+        self.memory.erase(start, self.slot_size)?;
+
+        // the header goes last, so a record is only valid once it's
+        // all there
+        let (length, mut crc) = {
+            let mut writer = Writer {
+                memory: &mut self.memory,
+                start: start + HEADER_SIZE,
+                capacity: self.slot_size - HEADER_SIZE,
+                length: 0,
+                crc: Crc32::new(),
+                buffer: [0; CHUNK_SIZE],
+                buffered: 0,
+            };
+            data.write(&mut writer)?;
+            writer.flush()?;
+            (writer.length, writer.crc)
+        };
+        let mut header = Header { sequence, length, version: T::version(), checksum: 0 };
+        crc.update(&header.bytes()[..CHECKED_HEADER_SIZE]);
+        header.checksum = crc.finish();
+        self.memory.write(start, &header.bytes())?;
+        Ok(())
+    }
+}
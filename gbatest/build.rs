@@ -0,0 +1,8 @@
+// build.rs
+
+extern crate gba_cartridge;
+
+fn main() {
+    // The cartridge header, from [package.metadata.gba] in Cargo.toml.
+    gba_cartridge::metadata::write_header().unwrap();
+}
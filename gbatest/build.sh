@@ -5,14 +5,20 @@ dir="$( cd "$( dirname "${BASH_SOURCE[0]}" )" && pwd )"
 set -e
 export RUST_TARGET_PATH=$dir
 
+args=()
 for i in "$@"
 do
 case $i in
     --release)
     release=true
+    args+=("$i")
+    ;;
+    --multiboot)
+    multiboot=true
     ;;
     *)
             # unknown option
+    args+=("$i")
     ;;
 esac
 done
@@ -23,5 +29,12 @@ else
     out_dir=$dir/target/gameboy-advance/debug
 fi
 
-xargo build --target gameboy-advance "$@"
-gba-make-cartridge $out_dir/$projname -o $out_dir/"$projname".gba
\ No newline at end of file
+if [ "$multiboot" = true ]; then
+    xargo build --target gameboy-advance --features multiboot "${args[@]}"
+    cargo run --manifest-path "$dir"/../gba-cartridge/Cargo.toml --release -- \
+          $out_dir/$projname -o $out_dir/"$projname".mb --multiboot
+else
+    xargo build --target gameboy-advance "${args[@]}"
+    cargo run --manifest-path "$dir"/../gba-cartridge/Cargo.toml --release -- \
+          $out_dir/$projname -o $out_dir/"$projname".gba --pad
+fi
\ No newline at end of file
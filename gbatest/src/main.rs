@@ -3,56 +3,58 @@
 #![no_main]
 
 extern crate alloc;
+#[macro_use]
 extern crate gba_rt;
 extern crate gba_bios;
 extern crate gba_hw;
 
-use gba_hw::{ReadWrite, ReadOnly};
+use gba_hw::interrupts::{KEYPAD, MasterEnable};
+use gba_hw::keypad::{KeyControlWrite, START, RIGHT};
+use gba_hw::video::{DisplayControlWrite, BG2};
+use gba_rt::{CriticalGba, Gba};
+use gba_rt::gba::Video;
 
+rom_header!(include!(concat!(env!("OUT_DIR"), "/header.rs")));
+
+fn on_keypad(_gba: &CriticalGba) {}
+
+fn fill(video: &mut Video, color: u16) {
+    for pixel in video.vram()[..240 * 160].iter_mut() {
+        *pixel = color;
+    }
+}
 
 #[no_mangle]
-pub fn main() -> ! {
+pub fn main(mut gba: Gba) -> ! {
     unsafe {
         gba_rt::init_heap();
+    }
 
-        let ie_reg = 0x4000200 as *mut ReadWrite<u16>;
-        let if_reg = 0x4000202 as *mut ReadWrite<u16>;
-        let ime_reg = 0x4000208 as *mut ReadWrite<u16>;
-//        let dispstat_reg = 0x4000004 as *const RW<u16>;
-        let keycnt_reg = 0x4000132 as *mut ReadWrite<u16>;
-        (*if_reg).write(1 << 12);
-        (*ie_reg).write(1 << 12);
-        (*ime_reg).write(1);
-        (*keycnt_reg).write(1 << 14 | 1 << 3);
-        let display_control = gba_hw::video::display_control();
-        let p_screen = 0x6000000 as *mut ReadWrite<u16>;
-        let keyinput_reg = 0x4000130 as *const ReadOnly<u16>;
-        display_control.write(
-            gba_hw::video::DisplayControlWrite::default()
-                .set_video_mode(3)
-                .set_display_layers(gba_hw::video::BG2));
-        for y in 0..160 {
-            for x in 0..240 {
-                (*p_screen.offset(x + y*240)).write(31 << 5);
-            }
-        }
-
-        while (*keyinput_reg).read() & 1 << 4 == 1 << 4 {}
-
-        for y in 0..160 {
-            for x in 0..240 {
-                (*p_screen.offset(x + y*240)).write(31);
-            }
-        }
-
-        gba_bios::wait_for_new_interrupt(gba_hw::interrupts::KEYPAD);
-
-        for y in 0..160 {
-            for x in 0..240 {
-                (*p_screen.offset(x + y*240)).write(31 << 10);
-            }
-        }
+    gba_rt::interrupts::set_handler(KEYPAD, Some(on_keypad));
+    gba.interrupts.enable().write(KEYPAD);
+    unsafe {
+        // outside any critical section
+        gba.interrupts.master_enable().write(MasterEnable::InterruptsEnabled);
     }
+    gba.keypad.control().write(
+        KeyControlWrite::default()
+            .set_keys(START)
+            .enable_irq());
+
+    gba.video.display_control().write(
+        DisplayControlWrite::default()
+            .set_video_mode(3)
+            .set_display_layers(BG2));
+    fill(&mut gba.video, 31 << 5);
+
+    while !gba.keypad.pressed().contains(RIGHT) {}
+
+    fill(&mut gba.video, 31);
+
+    gba_bios::try_wait_for_new_interrupt(KEYPAD).unwrap();
+
+    fill(&mut gba.video, 31 << 10);
+
     loop {
     }
-}
\ No newline at end of file
+}